@@ -0,0 +1,90 @@
+#![allow(clippy::needless_return)]
+
+// Lexer throughput benchmark. Run with `cargo bench --bench lexer`
+use std::time::{Duration, Instant};
+
+use golite::lexer::{Lexer, Token};
+
+// One chunk of GoLite exercising identifiers, keywords, every literal kind,
+// operators, and comments. {n} keeps the function names unique
+const CHUNK: &str = r#"
+// Computes a value from the inputs
+type point{n} struct {
+	x, y int
+	name string
+}
+
+/* Walks the slice and
+   sums what it finds */
+func sum{n}(xs []int, scale float64) float64 {
+	total := 0.0
+	for i := 0; i < len(xs); i++ {
+		if xs[i]%2 == 0 && xs[i] != 0x1F {
+			total += float64(xs[i]) * scale
+		} else {
+			total -= 1_000.5e-3
+		}
+	}
+	var r rune = '\n'
+	s := "héllo, wörld\té"
+	raw := `raw
+string`
+	switch {
+	case r == 'x':
+		println(s, raw)
+	default:
+		print(len(s) << 2 >> 1 &^ 0b1010)
+	}
+	return total
+}
+"#;
+
+// Builds a source file of roughly the given size in bytes
+fn generate(size: usize) -> String {
+    let mut src = String::from("package main\n");
+    let mut n = 0;
+    while src.len() < size {
+        src += &CHUNK.replace("{n}", &n.to_string());
+        n += 1;
+    }
+    return src;
+}
+
+// Lexes the source once, returning how many tokens it produced
+fn lex(src: &[u8]) -> usize {
+    let mut lex = Lexer::from_bytes(src);
+    let mut count = 0;
+    while !lex.next_token().same_type(&Token::EOF) {
+        count += 1;
+    }
+    return count;
+}
+
+// Runs the lexer repeatedly and reports the fastest run
+fn bench(name: &str, src: &[u8], runs: u32) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    for _ in 0..runs {
+        let start = Instant::now();
+        tokens = lex(std::hint::black_box(src));
+        best = best.min(start.elapsed());
+    }
+    let secs = best.as_secs_f64();
+    println!(
+        "{:<12} {:>9} bytes {:>8} tokens {:>9.3} ms {:>12.0} tokens/s {:>8.1} MB/s",
+        name,
+        src.len(),
+        tokens,
+        secs * 1e3,
+        tokens as f64 / secs,
+        src.len() as f64 / secs / 1e6,
+    );
+}
+
+fn main() {
+    bench("test.go", include_bytes!("../test.go"), 50);
+    for size in [64 << 10, 1 << 20, 16 << 20] {
+        let src = generate(size);
+        bench(&format!("gen-{}k", size >> 10), src.as_bytes(), 5);
+    }
+}
@@ -1,61 +1,93 @@
-use std::{collections::HashMap, string};
+use std::{collections::{BTreeMap, HashMap}, fmt};
 
-use super::Token;
+use crate::lexer::Bytes;
+use crate::symbol::Symbol;
 
+#[derive(Debug)]
 pub struct AST {
-    package: String,
-    main: Declaration,
-    func_env: HashMap<Token, Literal>,
-    val_env: HashMap<Token, Literal>,
-    type_env: HashMap<Token, Literal>,
+    pub package: Symbol,
+    pub decls: Vec<TopLevelDecl>,
+    // Each type's methods, as indexes into decls in declaration order, keyed
+    // by the receiver's type name
+    pub method_table: BTreeMap<Symbol, Vec<usize>>,
 }
 
+// The declarations that can appear at package level, kept in source order
+#[derive(Debug)]
+pub enum TopLevelDecl {
+    Import(Option<Symbol>, String), // The name it's imported as, which can be _ or ., and the path
+    Func(Symbol, FuncLiteral),
+    Method(Method),
+    Decl(Declaration),
+}
+
+// func (recv) name(params) results { body }
+#[derive(Debug)]
+pub struct Method {
+    pub recv: Receiver,
+    pub name: Symbol,
+    pub func: FuncLiteral,
+}
+
+// (s *Stack) or (s Stack). The name may be left out
+#[derive(Debug)]
+pub struct Receiver {
+    pub name: Option<Symbol>,
+    pub base: Symbol,
+    pub pointer: bool,
+}
+
+#[derive(Debug)]
 pub enum Expression {
     Unary(Operator, Box<Expression>),
     Binary(Box<Expression>, Operator, Box<Expression>),
     PrimaryExpr(Box<PrimaryExpr>),
 }
 
+#[derive(Debug)]
 pub enum PrimaryExpr {
-    Append(Expression, Expression),
-    Length(Expression),
-    Capacity(Expression),
     Operand(Operand),
-    Selector(Box<PrimaryExpr>, String),
+    Selector(Box<PrimaryExpr>, Symbol),
     Index(Box<PrimaryExpr>, Expression),
+    Slice(Box<PrimaryExpr>, Option<Expression>, Option<Expression>), // a[low:high]
     Call(Option<Box<PrimaryExpr>>, Option<Type>, Option<ExprList>), // Used by conversion and call
 }
 
+#[derive(Debug)]
 pub enum Operand {
     Literal(Literal),
-    Op(String),
+    Op(Symbol),
     Expr(Expression),
 }
 
+#[derive(Debug)]
 pub enum Statement {
     SimpleStmt(SimpleStmt),
     Decl(Declaration),
     StmtList(Box<StmtList>),
     PrintStmt(PrintType, Option<ExprList>),
-    ReturnStmt(Option<Expression>),
+    ReturnStmt(Option<ExprList>),
     IfStmt(Option<SimpleStmt>, Expression, Box<StmtList>, Option<Box<Statement>>),
     SwitchStmt(Option<SimpleStmt>, Option<Expression>, Vec<ExprCaseClause>),
     ForStmt(Option<ForClause>, StmtList),
+    RangeStmt(RangeClause, StmtList),
     BreakStmt,
     ContinueStmt,
     FallthroughStmt,
 }
 
+#[derive(Debug)]
 pub enum SimpleStmt {
     ExprStmt(Expression),
     IncDecStmt(Expression, IncDecType),
     Assignment(ExprList, Option<Operator>, ExprList),
-    ShortValDecl(Vec<String>, ExprList),
+    ShortValDecl(Vec<Symbol>, ExprList),
     EmptyStmt,
 }
 
+#[derive(Debug)]
 pub enum Declaration {
-    Type(Vec<(String, Type)>),
+    Type(Vec<(Symbol, Type)>),
     Var(Vec<VarSpec>),
     Parameter,
     Array,
@@ -63,42 +95,74 @@ pub enum Declaration {
     Struct,
 }
 
+#[derive(Debug)]
 pub enum Literal {
-    Integer(i32),
+    Integer(i64),
     Float(f64),
     Rune(char),
-    String(String),
-    Bool(bool),
-    Function(Option<ParamList>, Option<Token>, StmtList)
+    String(Bytes),
+    Function(Option<ParamList>, Option<ParamList>, StmtList),
+    Composite(CompositeLit),
 }
 
 #[derive(Clone, Debug)]
 pub enum Type {
-    Struct(String),
-    Array(i32, Box<Type>),
+    // A type written by name, to be looked up in scope. Once resolved, only the
+    // names of declared types are left, each standing for that type
+    Named(Symbol),
+    Struct(Vec<(Symbol, Type)>), // Fields in declaration order
+    Array(i64, Box<Type>),
     Slice(Box<Type>),
+    Pointer(Box<Type>),
     Integer,
-    Float,
+    Float, // float64
     String,
     Bool,
     Rune,
+    Nil, // The type of nil, which any pointer or slice can be compared with or set to
+}
+
+// Writes a type the way it's spelled in Go
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Named(name) => return write!(f, "{}", name),
+            Type::Struct(fields) => {
+                let fields: Vec<String> = fields.iter().map(|(name, ty)| format!("{} {}", name, ty)).collect();
+                return write!(f, "struct {{ {} }}", fields.join("; "));
+            }
+            Type::Array(len, elem) => return write!(f, "[{}]{}", len, elem),
+            Type::Slice(elem) => return write!(f, "[]{}", elem),
+            Type::Pointer(elem) => return write!(f, "*{}", elem),
+            Type::Integer => return write!(f, "int"),
+            Type::Float => return write!(f, "float64"),
+            Type::String => return write!(f, "string"),
+            Type::Bool => return write!(f, "bool"),
+            Type::Rune => return write!(f, "rune"),
+            Type::Nil => return write!(f, "nil"),
+        }
+    }
 }
 
+#[derive(Debug)]
 pub enum TypeDecl {
-    Struct(HashMap<String, Type>),
+    Struct(HashMap<Symbol, Type>),
     Primitive(Type)
 }
 
+#[derive(Debug)]
 pub enum PrintType {
     Print,
     Println
 }
 
+#[derive(Debug)]
 pub enum IncDecType {
     Inc,
     Dec
 }
 
+#[derive(Debug)]
 pub enum Operator {
     Plus,
     Times,
@@ -120,59 +184,120 @@ pub enum Operator {
     Greater,
     GreaterEqual,
     NotEqual,
+    Deref,  // Unary *
+    AddrOf, // Unary &
 }
 
 
 
+#[derive(Debug)]
 pub struct ExprList {
     pub exprs: Vec<Expression>
 }
 
+// T{a, b} or T{k: v}. The type is only left out for a literal nested in
+// another, where it comes from the outer literal's element type
+#[derive(Debug)]
+pub struct CompositeLit {
+    pub lit_type: Option<Type>,
+    pub elems: Vec<KeyedElement>,
+}
+
+#[derive(Debug)]
+pub struct KeyedElement {
+    pub key: Option<Element>,
+    pub value: Element,
+}
+
+// A field name or key is parsed as an expression too, since which it is
+// depends on the literal's type
+#[derive(Debug)]
+pub enum Element {
+    Expr(Expression),
+    Lit(CompositeLit),
+}
+
+#[derive(Debug)]
 pub struct ExprCaseClause {
     pub expr: Option<ExprList>,
     pub stmt: StmtList,
 }
 
 
+#[derive(Debug)]
 pub struct StmtList {
     pub stmts: Vec<Statement>
 }
 
+// Groups of names sharing a type. The names are empty for unnamed parameters
+#[derive(Debug)]
 pub struct ParamList {
-    pub params: Vec<(Vec<String>, Type)>
+    pub params: Vec<(Vec<Symbol>, Type)>
 }
 
+#[derive(Debug)]
 pub struct FuncLiteral {
     pub params: Option<ParamList>,
-    pub ret: Option<Type>,
+    pub ret: Option<ParamList>, // Results, with no names unless they're named
     pub body: StmtList,
 }
 
+// for key, value := range expr. The key and value are optional, and are only
+// declared when define is set; otherwise they're assigned to with =
+#[derive(Debug)]
+pub struct RangeClause {
+    pub key: Option<Expression>,
+    pub value: Option<Expression>,
+    pub define: bool,
+    pub expr: Expression,
+}
+
 // Can be a for clause or just a plain expr
+#[derive(Debug)]
 pub struct ForClause {
     pub stmt1: Option<SimpleStmt>,
     pub expr: Option<Expression>,
     pub stmt2: Option<SimpleStmt>
 }
 
+#[derive(Debug)]
 pub struct VarSpec {
-    pub ident_list: Vec<String>,
+    pub ident_list: Vec<Symbol>,
     pub var_type: Option<Type>,
     pub expr_list: Option<ExprList>
 }
 
+#[derive(Clone, Debug)]
 pub enum VarValue {
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    Float(f64),
     Rune(char),
-    String(String),
+    String(Bytes),
     Bool(bool),
-    Struct(HashMap<String, VarValue>),
+    Struct(HashMap<Symbol, VarValue>),
     Array(Vec<VarValue>),
+    Nil,
 }
 
 impl AST {
     pub fn print() {
 
     }
+
+    // Every method declared in the file, in source order
+    pub fn methods(&self) -> impl Iterator<Item = &Method> {
+        return self.decls.iter().filter_map(|decl| match decl {
+            TopLevelDecl::Method(method) => Some(method),
+            _ => None,
+        });
+    }
+
+    // The methods of one type, in source order
+    pub fn methods_of(&self, base: Symbol) -> impl Iterator<Item = &Method> {
+        let indexes = self.method_table.get(&base).map(|indexes| indexes.as_slice()).unwrap_or_default();
+        return indexes.iter().filter_map(|&i| match &self.decls[i] {
+            TopLevelDecl::Method(method) => Some(method),
+            _ => None,
+        });
+    }
 }
\ No newline at end of file
@@ -1,15 +1,43 @@
-use crate::{ast, lexer::Token};
+use std::fmt;
 
+use crate::lexer::{Span, Token};
 
+// An error found in the source, located by its span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub msg: String,
+    pub span: Span,
+}
 
-pub fn token_type_err(got:&Token, expected: &Token) {
-    panic!("Expected: {:?}\nGot: {:?}", expected, got);
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}: {}", self.span, self.msg);
+    }
 }
 
-pub fn ast_type_err(got: &str, expected: &str) {
-    panic!("Expected: {:?}\nGot: {:?}", expected, got);
+pub fn diagnostics_err(diags: &[Diagnostic]) -> ! {
+    let msgs: Vec<String> = diags.iter().map(|diag| diag.to_string()).collect();
+    panic!("{}", msgs.join("\n"));
+}
+
+pub fn token_type_err(got:&Token, expected: &Token, span: &Span) -> ! {
+    panic!("{}: Expected: {:?}\nGot: {:?}", span, expected, got);
+}
+
+// For when any token of a kind will do, such as an identifier
+pub fn expected_err(got: &Token, expected: &str, span: &Span) -> ! {
+    panic!("{}: Expected: {}\nGot: {:?}", span, expected, got);
+}
+
+// For a name declared twice where it has to be unique, like a struct field
+pub fn duplicate_err(kind: &str, name: &str, span: &Span) -> ! {
+    panic!("{}: duplicate {} {}", span, kind, name);
 }
 
-pub fn lit_err(got: &str, expected: &str) {
+pub fn ast_type_err(got: &str, expected: &str, span: &Span) -> ! {
+    panic!("{}: Expected: {:?}\nGot: {:?}", span, expected, got);
+}
+
+pub fn lit_err(got: &str, expected: &str) -> ! {
     panic!("Expected: {:?}\nGot: {:?}", expected, got);
-}
\ No newline at end of file
+}
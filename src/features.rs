@@ -0,0 +1,56 @@
+use crate::error::Diagnostic;
+use crate::lexer::{Lexer, Span, Token};
+
+// Names a Go construct that GoLite leaves out, and explains what to do instead.
+// GoLite lexes the full Go keyword set, so these still arrive as tokens
+fn unsupported(tok: &Token) -> Option<(&'static str, &'static str)> {
+    let feature = match tok {
+        Token::Chan => ("channel types", "GoLite has no concurrency, so there is nothing to communicate with"),
+        Token::LArrow => ("channel operations", "GoLite has no channels to send on or receive from"),
+        Token::Go => ("goroutines", "GoLite programs run on a single thread; call the function directly"),
+        Token::Select => ("select statements", "GoLite has no channels to wait on"),
+        Token::Goto => ("goto statements", "use for loops with break and continue instead"),
+        Token::Defer => ("deferred calls", "make the call explicitly before each return"),
+        Token::Interface => ("interface types", "GoLite only has concrete types; use a struct instead"),
+        Token::Map => ("map types", "use a slice, or a slice of structs holding key and value"),
+        Token::Const => ("constant declarations", "declare a variable with var instead"),
+        _ => return None,
+    };
+    return Some(feature);
+}
+
+// ... means three different things in Go, told apart by the tokens around it:
+// [...]T, f(s...), and func f(xs ...T)
+fn dots(prev: Option<&Token>, next: Option<&Token>) -> (&'static str, &'static str) {
+    let is = |tok: Option<&Token>, kind: &Token| tok.is_some_and(|tok| tok.same_type(kind));
+    if is(prev, &Token::LBrack) && is(next, &Token::RBrack) {
+        return ("inferred array lengths", "write the length out, as in [3]int");
+    } else if is(next, &Token::RParen) || is(next, &Token::Comma) {
+        return ("variadic arguments", "pass the elements one at a time");
+    }
+    return ("variadic parameters", "pass a slice instead");
+}
+
+// Reports every use of a Go construct outside the GoLite subset
+pub fn check(tokens: &[(Token, Span)]) -> Vec<Diagnostic> {
+    let mut diags = Vec::new();
+    for (i, (tok, span)) in tokens.iter().enumerate() {
+        let feature = match tok {
+            Token::Dots => {
+                let prev = i.checked_sub(1).map(|prev| &tokens[prev].0);
+                Some(dots(prev, tokens.get(i + 1).map(|(next, _)| next)))
+            }
+            _ => unsupported(tok),
+        };
+        if let Some((name, why)) = feature {
+            let msg = format!("feature {} is not part of GoLite: {}", name, why);
+            diags.push(Diagnostic { msg, span: span.clone() });
+        }
+    }
+    return diags;
+}
+
+// Runs the check over the rest of a lexer's input without consuming any of it
+pub fn check_lexer(lex: &mut Lexer) -> Vec<Diagnostic> {
+    return check(lex.remaining());
+}
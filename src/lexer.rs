@@ -1,6 +1,12 @@
+use crate::error::Diagnostic;
+use crate::symbol::Symbol;
+
 use std::{
+    borrow::Cow,
+    fmt,
     fs,
-    io::{BufReader, Read},
+    io::{self, Read},
+    rc::Rc,
 };
 
 #[derive(Debug)]
@@ -33,9 +39,6 @@ pub enum Token {
     Var,
     Print,
     Println,
-    Append,
-    Len,
-    Cap,
 
     // Operators
     Plus,
@@ -87,22 +90,51 @@ pub enum Token {
     Colon,
 
     //Literals
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    Float(f64),
     Rune(char),
-    String(String),
-    Bool(bool),
+    String(Bytes),
 
-    // Error(string, line)
-    Error(String, i32),
 
     // Misc
-    Ident(String),
+    Ident(Symbol),
     BlankIdent,
     EOF,
     Empty,
 }
 
+// The value of a string literal. Go strings hold arbitrary bytes, and escapes
+// like \xff can spell out bytes that aren't valid UTF-8
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+    // The text, if the bytes are valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        return std::str::from_utf8(&self.0).ok();
+    }
+}
+
+impl From<&str> for Bytes {
+    fn from(text: &str) -> Self {
+        return Bytes(text.as_bytes().to_vec());
+    }
+}
+
+// Shows the bytes as a quoted string, escaping any that aren't UTF-8 as \xNN
+impl fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for chunk in self.0.utf8_chunks() {
+            write!(f, "{}", chunk.valid().escape_debug())?;
+            for byte in chunk.invalid() {
+                write!(f, "\\x{:02x}", byte)?;
+            }
+        }
+        return f.write_str("\"");
+    }
+}
+
 impl PartialEq for Token {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -110,8 +142,6 @@ impl PartialEq for Token {
             (Self::Float(l0), Self::Float(r0)) => l0 == r0,
             (Self::Rune(l0), Self::Rune(r0)) => l0 == r0,
             (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
-            (Self::Error(l0, l1), Self::Error(r0, r1)) => l0 == r0 && l1 == r1,
             (Self::Ident(l0), Self::Ident(r0)) => l0 == r0,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
@@ -148,9 +178,6 @@ impl Clone for Token {
             Self::Var => Self::Var,
             Self::Print => Self::Print,
             Self::Println => Self::Println,
-            Self::Append => Self::Append,
-            Self::Len => Self::Len,
-            Self::Cap => Self::Cap,
             Self::Plus => Self::Plus,
             Self::Minus => Self::Minus,
             Self::Aster => Self::Aster,
@@ -198,13 +225,11 @@ impl Clone for Token {
             Self::Period => Self::Period,
             Self::Semicolon => Self::Semicolon,
             Self::Colon => Self::Colon,
-            Self::Int(arg0) => Self::Int(arg0.clone()),
-            Self::Float(arg0) => Self::Float(arg0.clone()),
-            Self::Rune(arg0) => Self::Rune(arg0.clone()),
+            Self::Int(arg0) => Self::Int(*arg0),
+            Self::Float(arg0) => Self::Float(*arg0),
+            Self::Rune(arg0) => Self::Rune(*arg0),
             Self::String(arg0) => Self::String(arg0.clone()),
-            Self::Bool(arg0) => Self::Bool(arg0.clone()),
-            Self::Error(arg0, arg1) => Self::Error(arg0.clone(), arg1.clone()),
-            Self::Ident(arg0) => Self::Ident(arg0.clone()),
+            Self::Ident(arg0) => Self::Ident(*arg0),
             Self::BlankIdent => Self::BlankIdent,
             Self::EOF => Self::EOF,
             Self::Empty => Self::Empty,
@@ -212,174 +237,680 @@ impl Clone for Token {
     }
 }
 
+const BOM: char = '\u{FEFF}';
+
+// Describes a numeric literal by its base prefix
+fn literal_name(prefix: char) -> &'static str {
+    match prefix {
+        'x' => "hexadecimal literal",
+        'o' | '0' => "octal literal",
+        'b' => "binary literal",
+        _ => "decimal literal",
+    }
+}
+
+// Checks that every '_' in a numeric literal sits between two digits, or
+// between the base prefix and a digit
+fn invalid_separator(lit: &str) -> bool {
+    let chars: Vec<char> = lit.chars().collect();
+    let mut hex = false;
+    // The previous character: '0' for a digit, '_' or '.' for anything else
+    let mut prev = '.';
+    let mut i = 0;
+    if chars.len() >= 2 && chars[0] == '0' {
+        let x = chars[1].to_ascii_lowercase();
+        hex = x == 'x';
+        if x == 'x' || x == 'o' || x == 'b' {
+            prev = '0';
+            i = 2;
+        }
+    }
+    while i < chars.len() {
+        let chr = chars[i];
+        if chr == '_' {
+            if prev != '0' {
+                return true;
+            }
+            prev = '_';
+        } else if chr.is_ascii_digit() || (hex && chr.is_ascii_hexdigit()) {
+            prev = '0';
+        } else {
+            if prev == '_' {
+                return true;
+            }
+            prev = '.';
+        }
+        i += 1;
+    }
+    return prev == '_';
+}
+
+// Converts the mantissa and exponent of a hexadecimal float, without its 0x prefix
+fn parse_hex_float(lit: &str) -> f64 {
+    let (mantissa, exp) = lit.split_once(['p', 'P']).unwrap_or((lit, "0"));
+    let mut val: f64 = 0.0;
+    let mut scale: i32 = 0;
+    let mut fraction = false;
+    for chr in mantissa.chars() {
+        if chr == '.' {
+            fraction = true;
+            continue;
+        }
+        val = val * 16.0 + chr.to_digit(16).unwrap_or(0) as f64;
+        if fraction {
+            scale -= 4;
+        }
+    }
+    let exp: i32 = exp.parse().unwrap_or(i32::MAX);
+    return val * 2f64.powi(exp.saturating_add(scale));
+}
+
+fn invalid_char(chr: char) -> String {
+    return format!("invalid character U+{:04X} {:?}", chr as u32, chr);
+}
+
+// Every run of characters with the Alphabetic property that aren't letters,
+// as of Unicode 14.0: letter numbers (Nl) such as Ⅻ, and the marks and
+// symbols (Mn, Mc, So) that are Other_Alphabetic, such as the iota in aͅ
+const NON_LETTERS: [(u32, u32); 246] = [
+    (0x345, 0x345), (0x363, 0x36F), (0x5B0, 0x5BD), (0x5BF, 0x5BF), (0x5C1, 0x5C2),
+    (0x5C4, 0x5C5), (0x5C7, 0x5C7), (0x610, 0x61A), (0x64B, 0x657), (0x659, 0x65F),
+    (0x670, 0x670), (0x6D6, 0x6DC), (0x6E1, 0x6E4), (0x6E7, 0x6E8), (0x6ED, 0x6ED),
+    (0x711, 0x711), (0x730, 0x73F), (0x7A6, 0x7B0), (0x816, 0x817), (0x81B, 0x823),
+    (0x825, 0x827), (0x829, 0x82C), (0x8D4, 0x8DF), (0x8E3, 0x8E9), (0x8F0, 0x903),
+    (0x93A, 0x93B), (0x93E, 0x94C), (0x94E, 0x94F), (0x955, 0x957), (0x962, 0x963),
+    (0x981, 0x983), (0x9BE, 0x9C4), (0x9C7, 0x9C8), (0x9CB, 0x9CC), (0x9D7, 0x9D7),
+    (0x9E2, 0x9E3), (0xA01, 0xA03), (0xA3E, 0xA42), (0xA47, 0xA48), (0xA4B, 0xA4C),
+    (0xA51, 0xA51), (0xA70, 0xA71), (0xA75, 0xA75), (0xA81, 0xA83), (0xABE, 0xAC5),
+    (0xAC7, 0xAC9), (0xACB, 0xACC), (0xAE2, 0xAE3), (0xAFA, 0xAFC), (0xB01, 0xB03),
+    (0xB3E, 0xB44), (0xB47, 0xB48), (0xB4B, 0xB4C), (0xB56, 0xB57), (0xB62, 0xB63),
+    (0xB82, 0xB82), (0xBBE, 0xBC2), (0xBC6, 0xBC8), (0xBCA, 0xBCC), (0xBD7, 0xBD7),
+    (0xC00, 0xC04), (0xC3E, 0xC44), (0xC46, 0xC48), (0xC4A, 0xC4C), (0xC55, 0xC56),
+    (0xC62, 0xC63), (0xC81, 0xC83), (0xCBE, 0xCC4), (0xCC6, 0xCC8), (0xCCA, 0xCCC),
+    (0xCD5, 0xCD6), (0xCE2, 0xCE3), (0xD00, 0xD03), (0xD3E, 0xD44), (0xD46, 0xD48),
+    (0xD4A, 0xD4C), (0xD57, 0xD57), (0xD62, 0xD63), (0xD81, 0xD83), (0xDCF, 0xDD4),
+    (0xDD6, 0xDD6), (0xDD8, 0xDDF), (0xDF2, 0xDF3), (0xE31, 0xE31), (0xE34, 0xE3A),
+    (0xE4D, 0xE4D), (0xEB1, 0xEB1), (0xEB4, 0xEB9), (0xEBB, 0xEBC), (0xECD, 0xECD),
+    (0xF71, 0xF83), (0xF8D, 0xF97), (0xF99, 0xFBC), (0x102B, 0x1036), (0x1038, 0x1038),
+    (0x103B, 0x103E), (0x1056, 0x1059), (0x105E, 0x1060), (0x1062, 0x1064),
+    (0x1067, 0x106D), (0x1071, 0x1074), (0x1082, 0x108D), (0x108F, 0x108F),
+    (0x109A, 0x109D), (0x16EE, 0x16F0), (0x1712, 0x1713), (0x1732, 0x1733),
+    (0x1752, 0x1753), (0x1772, 0x1773), (0x17B6, 0x17C8), (0x1885, 0x1886),
+    (0x18A9, 0x18A9), (0x1920, 0x192B), (0x1930, 0x1938), (0x1A17, 0x1A1B),
+    (0x1A55, 0x1A5E), (0x1A61, 0x1A74), (0x1ABF, 0x1AC0), (0x1ACC, 0x1ACE),
+    (0x1B00, 0x1B04), (0x1B35, 0x1B43), (0x1B80, 0x1B82), (0x1BA1, 0x1BA9),
+    (0x1BAC, 0x1BAD), (0x1BE7, 0x1BF1), (0x1C24, 0x1C36), (0x1DD3, 0x1DF4),
+    (0x2160, 0x2182), (0x2185, 0x2188), (0x24B6, 0x24E9), (0x2DE0, 0x2DFF),
+    (0x3007, 0x3007), (0x3021, 0x3029), (0x3038, 0x303A), (0xA674, 0xA67B),
+    (0xA69E, 0xA69F), (0xA6E6, 0xA6EF), (0xA802, 0xA802), (0xA80B, 0xA80B),
+    (0xA823, 0xA827), (0xA880, 0xA881), (0xA8B4, 0xA8C3), (0xA8C5, 0xA8C5),
+    (0xA8FF, 0xA8FF), (0xA926, 0xA92A), (0xA947, 0xA952), (0xA980, 0xA983),
+    (0xA9B4, 0xA9BF), (0xA9E5, 0xA9E5), (0xAA29, 0xAA36), (0xAA43, 0xAA43),
+    (0xAA4C, 0xAA4D), (0xAA7B, 0xAA7D), (0xAAB0, 0xAAB0), (0xAAB2, 0xAAB4),
+    (0xAAB7, 0xAAB8), (0xAABE, 0xAABE), (0xAAEB, 0xAAEF), (0xAAF5, 0xAAF5),
+    (0xABE3, 0xABEA), (0xFB1E, 0xFB1E), (0x10140, 0x10174), (0x10341, 0x10341),
+    (0x1034A, 0x1034A), (0x10376, 0x1037A), (0x103D1, 0x103D5), (0x10A01, 0x10A03),
+    (0x10A05, 0x10A06), (0x10A0C, 0x10A0F), (0x10D24, 0x10D27), (0x10EAB, 0x10EAC),
+    (0x11000, 0x11002), (0x11038, 0x11045), (0x11073, 0x11074), (0x11080, 0x11082),
+    (0x110B0, 0x110B8), (0x110C2, 0x110C2), (0x11100, 0x11102), (0x11127, 0x11132),
+    (0x11145, 0x11146), (0x11180, 0x11182), (0x111B3, 0x111BF), (0x111CE, 0x111CF),
+    (0x1122C, 0x11234), (0x11237, 0x11237), (0x1123E, 0x1123E), (0x112DF, 0x112E8),
+    (0x11300, 0x11303), (0x1133E, 0x11344), (0x11347, 0x11348), (0x1134B, 0x1134C),
+    (0x11357, 0x11357), (0x11362, 0x11363), (0x11435, 0x11441), (0x11443, 0x11445),
+    (0x114B0, 0x114C1), (0x115AF, 0x115B5), (0x115B8, 0x115BE), (0x115DC, 0x115DD),
+    (0x11630, 0x1163E), (0x11640, 0x11640), (0x116AB, 0x116B5), (0x1171D, 0x1172A),
+    (0x1182C, 0x11838), (0x11930, 0x11935), (0x11937, 0x11938), (0x1193B, 0x1193C),
+    (0x11940, 0x11940), (0x11942, 0x11942), (0x119D1, 0x119D7), (0x119DA, 0x119DF),
+    (0x119E4, 0x119E4), (0x11A01, 0x11A0A), (0x11A35, 0x11A39), (0x11A3B, 0x11A3E),
+    (0x11A51, 0x11A5B), (0x11A8A, 0x11A97), (0x11C2F, 0x11C36), (0x11C38, 0x11C3E),
+    (0x11C92, 0x11CA7), (0x11CA9, 0x11CB6), (0x11D31, 0x11D36), (0x11D3A, 0x11D3A),
+    (0x11D3C, 0x11D3D), (0x11D3F, 0x11D41), (0x11D43, 0x11D43), (0x11D47, 0x11D47),
+    (0x11D8A, 0x11D8E), (0x11D90, 0x11D91), (0x11D93, 0x11D96), (0x11EF3, 0x11EF6),
+    (0x12400, 0x1246E), (0x16F4F, 0x16F4F), (0x16F51, 0x16F87), (0x16F8F, 0x16F92),
+    (0x16FF0, 0x16FF1), (0x1BC9E, 0x1BC9E), (0x1E000, 0x1E006), (0x1E008, 0x1E018),
+    (0x1E01B, 0x1E021), (0x1E023, 0x1E024), (0x1E026, 0x1E02A), (0x1E947, 0x1E947),
+    (0x1F130, 0x1F149), (0x1F150, 0x1F169), (0x1F170, 0x1F189),
+];
+
+// Letters as defined by the Go spec, categories Lu, Ll, Lt, Lm, and Lo, which
+// also counts the underscore. char::is_alphabetic is close, but takes the
+// characters in NON_LETTERS too
+fn is_letter(chr: char) -> bool {
+    if chr.is_ascii() {
+        return chr.is_ascii_alphabetic() || chr == '_';
+    }
+    if !chr.is_alphabetic() {
+        return false;
+    }
+    let code = chr as u32;
+    let run = NON_LETTERS.partition_point(|&(first, _)| first <= code);
+    return run == 0 || code > NON_LETTERS[run - 1].1;
+}
+
+// The zero of every run of decimal digits (category Nd) as of Unicode 15.0,
+// the version Go uses. Each run holds the ten digits in order
+const DIGIT_ZEROS: [u32; 68] = [
+    0x30, 0x660, 0x6F0, 0x7C0, 0x966, 0x9E6, 0xA66, 0xAE6, 0xB66, 0xBE6, 0xC66, 0xCE6,
+    0xD66, 0xDE6, 0xE50, 0xED0, 0xF20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0,
+    0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0,
+    0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136,
+    0x111D0, 0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950,
+    0x11C50, 0x11D50, 0x11DA0, 0x11F50, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8,
+    0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E950, 0x1FBF0,
+];
+
+// Digits as defined by the Go spec. char::is_numeric would also take other
+// numeric characters such as ², which aren't decimal digits
+fn is_digit(chr: char) -> bool {
+    if chr.is_ascii() {
+        return chr.is_ascii_digit();
+    }
+    let code = chr as u32;
+    let run = DIGIT_ZEROS.partition_point(|&zero| zero <= code);
+    return run > 0 && code - DIGIT_ZEROS[run - 1] < 10;
+}
+
 impl Token {
     pub fn same_type(&self, other: &Self) -> bool {
         return core::mem::discriminant(self) == core::mem::discriminant(other);
     }
+
+    pub fn is_ident(&self) -> bool {
+        return matches!(self, Token::Ident(_));
+    }
+
+    // The variant's name without its value, such as Ident or Semicolon
+    pub fn kind(&self) -> &'static str {
+        return match self {
+            Token::Break => "Break",
+            Token::Case => "Case",
+            Token::Chan => "Chan",
+            Token::Const => "Const",
+            Token::Continue => "Continue",
+            Token::Default => "Default",
+            Token::Defer => "Defer",
+            Token::Else => "Else",
+            Token::Fallthrough => "Fallthrough",
+            Token::Func => "Func",
+            Token::For => "For",
+            Token::Go => "Go",
+            Token::Goto => "Goto",
+            Token::If => "If",
+            Token::Import => "Import",
+            Token::Interface => "Interface",
+            Token::Map => "Map",
+            Token::Package => "Package",
+            Token::Range => "Range",
+            Token::Return => "Return",
+            Token::Select => "Select",
+            Token::Struct => "Struct",
+            Token::Switch => "Switch",
+            Token::Type => "Type",
+            Token::Var => "Var",
+            Token::Print => "Print",
+            Token::Println => "Println",
+            Token::Plus => "Plus",
+            Token::Minus => "Minus",
+            Token::Aster => "Aster",
+            Token::FSlash => "FSlash",
+            Token::Percent => "Percent",
+            Token::Amper => "Amper",
+            Token::Or => "Or",
+            Token::Xor => "Xor",
+            Token::LShift => "LShift",
+            Token::RShift => "RShift",
+            Token::AndNot => "AndNot",
+            Token::PlusEqual => "PlusEqual",
+            Token::MinusEqual => "MinusEqual",
+            Token::TimesEqual => "TimesEqual",
+            Token::DivEqual => "DivEqual",
+            Token::ModEqual => "ModEqual",
+            Token::AndEqual => "AndEqual",
+            Token::OrEqual => "OrEqual",
+            Token::XorEqual => "XorEqual",
+            Token::LShiftEqual => "LShiftEqual",
+            Token::RShiftEqual => "RShiftEqual",
+            Token::AndNotEqual => "AndNotEqual",
+            Token::LogAnd => "LogAnd",
+            Token::LogOr => "LogOr",
+            Token::LArrow => "LArrow",
+            Token::PlusPlus => "PlusPlus",
+            Token::MinusMinus => "MinusMinus",
+            Token::EqualEqual => "EqualEqual",
+            Token::Less => "Less",
+            Token::Greater => "Greater",
+            Token::Equal => "Equal",
+            Token::Bang => "Bang",
+            Token::BangEqual => "BangEqual",
+            Token::LessEqual => "LessEqual",
+            Token::GreaterEqual => "GreaterEqual",
+            Token::Assignment => "Assignment",
+            Token::Dots => "Dots",
+            Token::LParen => "LParen",
+            Token::RParen => "RParen",
+            Token::LBrack => "LBrack",
+            Token::RBrack => "RBrack",
+            Token::LBrace => "LBrace",
+            Token::RBrace => "RBrace",
+            Token::Comma => "Comma",
+            Token::Period => "Period",
+            Token::Semicolon => "Semicolon",
+            Token::Colon => "Colon",
+            Token::Int(_) => "Int",
+            Token::Float(_) => "Float",
+            Token::Rune(_) => "Rune",
+            Token::String(_) => "String",
+            Token::Ident(_) => "Ident",
+            Token::BlankIdent => "BlankIdent",
+            Token::EOF => "EOF",
+            Token::Empty => "Empty",
+        };
+    }
+}
+
+// A region of source text, located by byte offsets and by the 1-based line and column it starts at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file: Rc<str>,
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}:{}:{}", self.file, self.line, self.col);
+    }
+}
+
+// Source text between tokens that carries no meaning for the parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    // Characters that were reported as errors and skipped
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
 }
 
+// A token together with its exact source text and the trivia before it
+#[derive(Debug, Clone)]
+pub struct TriviaToken {
+    pub tok: Token,
+    pub span: Span,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+}
+
+impl fmt::Display for TriviaToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(&trivia.text)?;
+        }
+        return f.write_str(&self.text);
+    }
+}
+
+// Splits the text between two tokens into whitespace, comments, and skipped characters
+fn split_trivia(text: &str) -> Vec<Trivia> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (kind, len);
+        if rest.starts_with("//") {
+            kind = TriviaKind::LineComment;
+            len = rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            kind = TriviaKind::BlockComment;
+            len = body.find("*/").map_or(rest.len(), |i| i + 4);
+        } else if rest.starts_with(char::is_whitespace) {
+            kind = TriviaKind::Whitespace;
+            len = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        } else {
+            kind = TriviaKind::Skipped;
+            len = rest
+                .char_indices()
+                .find(|(i, c)| c.is_whitespace() || rest[*i..].starts_with("//") || rest[*i..].starts_with("/*"))
+                .map_or(rest.len(), |(i, _)| i);
+        }
+        out.push(Trivia {
+            kind,
+            text: rest[..len].to_string(),
+        });
+        rest = &rest[len..];
+    }
+    return out;
+}
+
+// A position in the token stream saved by Lexer::checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
 pub struct Lexer {
-    reader: BufReader<fs::File>,
+    // The whole input; tokens and trivia are sliced straight out of it
+    source: Vec<u8>,
+    file: Rc<str>,
     chr: char,
     end: bool,
-    line: i32,
-    prev_token: Token,
-    peek_tok: Token,
+    // Whether chr was decoded from a malformed UTF-8 sequence
+    bad_utf8: bool,
+    // Position of chr; columns count characters, not bytes
+    offset: usize,
+    width: usize,
+    line: u32,
+    col: u32,
+    newline: bool,
+    // Whether a newline after the last token ends the statement
+    insert_semi: bool,
+    // Every token scanned so far; tokens[pos] is the one peek returns
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    // Offset, line, and column where the token being scanned starts
+    start: (usize, u32, u32),
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Lexer {
-    pub fn new(mut reader: BufReader<fs::File>) -> Self {
-        let mut buf: [u8; 1] = [0];
-        let end = reader.read(&mut buf).expect("Couldn't read file") == 0;
+    // Creates a lexer reading from any byte source (files, sockets, pipes, ...).
+    // The whole input is read up front, so the reader isn't kept
+    pub fn new<R: Read>(reader: R) -> io::Result<Self> {
+        return Self::named(reader, "<input>");
+    }
+
+    // Creates a lexer whose spans refer to the given file name
+    pub fn named<R: Read>(mut reader: R, file: &str) -> io::Result<Self> {
+        let mut source = Vec::new();
+        reader.read_to_end(&mut source)?;
+        return Ok(Self::with_source(source, file));
+    }
+
+    fn with_source(source: Vec<u8>, file: &str) -> Self {
+        let file: Rc<str> = Rc::from(file);
         let mut lex = Self {
-            reader,
-            end,
-            chr: buf[0] as char,
-            line: 0,
-            prev_token: Token::Empty,
-            peek_tok: Token::Empty,
+            source,
+            file,
+            end: false,
+            bad_utf8: false,
+            chr: '\0',
+            offset: 0,
+            width: 0,
+            line: 1,
+            col: 0,
+            newline: false,
+            insert_semi: false,
+            tokens: Vec::new(),
+            pos: 0,
+            start: (0, 1, 1),
+            diagnostics: Vec::new(),
         };
-        lex.next_token();
+        lex.next_char();
+
+        // A byte order mark is only allowed as the very first character
+        if lex.chr == BOM {
+            lex.next_char();
+            lex.col = 1;
+        }
+        let first = lex.scan_next();
+        lex.tokens.push(first);
         return lex;
     }
 
+    // Creates a lexer reading the file at the given path
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        return Ok(Self::with_source(fs::read(path)?, path));
+    }
+
+    // Creates a lexer over an in-memory source string
+    pub fn from_source(src: &str) -> Self {
+        return Self::from_bytes(src.as_bytes());
+    }
+
+    // Creates a lexer over an in-memory byte buffer
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        return Self::with_source(bytes.to_vec(), "<input>");
+    }
+
+    // Creates a lexer reading from standard input
+    pub fn from_stdin() -> io::Result<Self> {
+        return Self::named(io::stdin(), "<stdin>");
+    }
+
     pub fn eat(&mut self, tok: &Token) {
         if !self.peek().same_type(tok) {
-            panic!("Expected: {:?}; Got{:?}", self.peek(), tok);
+            panic!("{}: Expected: {:?}; Got: {:?}", self.peek_span(), tok, self.peek());
         }
         self.next_token();
     }
 
     pub fn peek(&self) -> &Token {
-        return &self.peek_tok;
+        return &self.tokens[self.pos].0;
+    }
+
+    // Span of the token returned by peek
+    pub fn peek_span(&self) -> &Span {
+        return &self.tokens[self.pos].1;
+    }
+
+    // Looks k tokens past peek without consuming anything; peek_nth(0) is peek
+    pub fn peek_nth(&mut self, k: usize) -> &Token {
+        self.fill(self.pos + k);
+        return &self.tokens[self.pos + k].0;
+    }
+
+    // Span of the token returned by peek_nth
+    pub fn peek_nth_span(&mut self, k: usize) -> &Span {
+        self.fill(self.pos + k);
+        return &self.tokens[self.pos + k].1;
+    }
+
+    // Span of the token most recently returned by next_token
+    pub fn span(&self) -> &Span {
+        return &self.tokens[self.pos.saturating_sub(1)].1;
+    }
+
+    // Marks the current position in the token stream so it can be returned to
+    pub fn checkpoint(&self) -> Checkpoint {
+        return Checkpoint(self.pos);
+    }
+
+    // Moves back to a checkpoint; the tokens after it will be returned again
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+
+    // Lexical errors found so far. The lexer scans one token ahead of next_token
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        return &self.diagnostics;
+    }
+
+    // The source text a span covers, exactly as written
+    pub fn lexeme(&self, span: &Span) -> Cow<'_, str> {
+        return String::from_utf8_lossy(&self.source[span.start..span.end]);
+    }
+
+    // Lexes the rest of the input like tokenize, but keeps the whitespace and comments
+    // between tokens attached to the token that follows them. Printing every token in
+    // order reproduces valid UTF-8 source byte for byte
+    pub fn tokenize_with_trivia(mut self) -> (Vec<TriviaToken>, Vec<Diagnostic>) {
+        let mut prev_end = if self.pos == 0 { 0 } else { self.span().end };
+        let mut out = Vec::new();
+        loop {
+            let tok = self.next_token();
+            let span = self.span().clone();
+            let end = tok.same_type(&Token::EOF);
+            let text = self.lexeme(&span).into_owned();
+            let leading = split_trivia(&String::from_utf8_lossy(&self.source[prev_end..span.start]));
+            prev_end = span.end;
+            out.push(TriviaToken { tok, span, text, leading });
+            if end {
+                return (out, self.diagnostics);
+            }
+        }
+    }
+
+    // Lexes the rest of the input, returning every token up to and including EOF
+    // along with all lexical errors found
+    pub fn tokenize(mut self) -> (Vec<(Token, Span)>, Vec<Diagnostic>) {
+        let mut toks = Vec::new();
+        loop {
+            let tok = self.next_token();
+            let end = tok.same_type(&Token::EOF);
+            toks.push((tok, self.span().clone()));
+            if end {
+                return (toks, self.diagnostics);
+            }
+        }
+    }
+
+    // Buffers the rest of the input and returns every token from the current
+    // position through EOF, without consuming any of them
+    pub fn remaining(&mut self) -> &[(Token, Span)] {
+        while !self.tokens.last().unwrap().0.same_type(&Token::EOF) {
+            let next = self.scan_next();
+            self.tokens.push(next);
+        }
+        return &self.tokens[self.pos..];
     }
 
     // Returns the next token in the token stream
     pub fn next_token(&mut self) -> Token {
-        let out = self.peek_tok.clone();
-        if self.end {
-            self.peek_tok = Token::EOF;
-            return out;
+        let tok = self.tokens[self.pos].0.clone();
+        self.pos += 1;
+        self.fill(self.pos);
+        return tok;
+    }
+
+    // Scans until the token at index i has been buffered
+    fn fill(&mut self, i: usize) {
+        while self.tokens.len() <= i {
+            let next = self.scan_next();
+            self.tokens.push(next);
         }
+    }
 
-        println!("{}", self.chr);
-        println!("Peek: {:?}", self.peek_tok);
-        // Keywords and Identifiers
-        if self.chr.is_alphabetic() {
-            let name = self.get_ident();
-            let tok = self.get_keyword(&name);
-            self.prev_token = tok.clone();
+    // Scans the next token along with the span of source it covers
+    fn scan_next(&mut self) -> (Token, Span) {
+        loop {
             self.skip_whitespace();
+            self.start = (self.offset, self.line, self.col);
+            let tok;
 
-            if let Token::Ident(str) = tok {
-                if str == "_" {
-                    return Token::BlankIdent;
-                }
-                self.peek_tok = Token::Ident(name);
-                return out;
-            } else {
-                println!("{:?}", tok);
-                self.peek_tok = tok;
-                return out;
+            // A newline or the end of the file terminates the statement
+            if self.insert_semi && (self.chr == '\n' || self.end) {
+                return self.inserted_semicolon();
             }
-        }
-        // Numbers
-        else if self.chr.is_numeric() {
-            if self.chr == '0' {
+            // Comments produce no token, but act like the newlines they contain
+            else if self.chr == '/' && self.peek_byte() == Some(b'/') {
+                self.skip_comment();
+                continue;
+            } else if self.chr == '/' && self.peek_byte() == Some(b'*') {
                 self.next_char();
-                match self.chr {
-                    'b' | 'B' => {
-                        self.next_char();
-                        let num = self.get_number(2);
-                        self.prev_token = Token::Int(num);
-                        self.skip_whitespace();
-                        self.peek_tok = Token::Int(num);
-                        return out;
-                    }
-                    'x' | 'X' => {
-                        self.next_char();
-                        let num = self.get_number(16);
-                        self.prev_token = Token::Int(num);
-                        self.skip_whitespace();
-                        self.peek_tok = Token::Int(num);
-                        return out;
-                    }
-                    'o' | 'O' => {
-                        self.next_char();
-                        let num = self.get_number(8);
-                        self.prev_token = Token::Int(num);
-                        self.skip_whitespace();
-                        self.peek_tok = Token::Int(num);
-                        return out;
-                    }
-                    _ => {}
+                let newline = self.skip_multiline_comment();
+                if newline && self.insert_semi {
+                    return self.inserted_semicolon();
+                }
+                continue;
+            } else {
+                tok = self.scan_token();
+
+                // Characters that can't start a token were reported and skipped
+                if tok.same_type(&Token::Empty) {
+                    continue;
                 }
             }
-            let num = self.get_number(10);
-            self.prev_token = Token::Empty;
-            self.skip_whitespace();
 
-            if self.chr == '.' {
-                self.next_char();
-                let decimal = self.get_number(10);
-                self.prev_token = Token::Empty;
-                self.skip_whitespace();
-                self.peek_tok = Token::Float(
-                    (num.to_string() + "." + &decimal.to_string())
-                        .parse()
-                        .unwrap(),
-                );
-                return out;
+            self.insert_semi = matches!(
+                tok,
+                Token::Ident(_)
+                    | Token::BlankIdent
+                    | Token::Int(_)
+                    | Token::Float(_)
+                    | Token::Rune(_)
+                    | Token::String(_)
+                    | Token::Break
+                    | Token::Continue
+                    | Token::Fallthrough
+                    | Token::Return
+                    | Token::PlusPlus
+                    | Token::MinusMinus
+                    | Token::RParen
+                    | Token::RBrack
+                    | Token::RBrace
+            );
+            return (tok, self.span_from_start());
+        }
+    }
+
+    // Automatically inserted semicolons take up no source text, so the newline
+    // or comment that caused them stays trivia of the next token
+    fn inserted_semicolon(&mut self) -> (Token, Span) {
+        self.insert_semi = false;
+        let mut span = self.span_from_start();
+        span.end = span.start;
+        return (Token::Semicolon, span);
+    }
+
+    // Span from the start of the current token up to the current character
+    fn span_from_start(&self) -> Span {
+        return Span {
+            file: self.file.clone(),
+            start: self.start.0,
+            end: self.offset,
+            line: self.start.1,
+            col: self.start.2,
+        };
+    }
+
+    // Records a lexical error on the current token and returns the token to recover with
+    fn error(&mut self, msg: String, recover: Token) -> Token {
+        let span = self.span_from_start();
+        self.diagnostics.push(Diagnostic { msg, span });
+        return recover;
+    }
+
+    fn scan_token(&mut self) -> Token {
+        if self.end {
+            return Token::EOF;
+        }
+
+        // Keywords and Identifiers
+        if is_letter(self.chr) {
+            let name = self.get_ident();
+            if let Some(tok) = Self::get_keyword(name) {
+                return tok;
+            }
+            if name == "_" {
+                return Token::BlankIdent;
             }
-            self.peek_tok = Token::Int(num);
-            return out;
+            return Token::Ident(Symbol::intern(name));
+        }
+        // Numbers
+        else if self.chr.is_ascii_digit() {
+            let tok = self.get_number(String::new());
+            return tok;
         }
         // Operators
         else if self.chr.is_ascii_punctuation() {
-            // Perhaps negative numbers
             if self.chr == '-' {
                 self.next_char();
-                if self.chr.is_digit(10) {
-                    let num = self.get_number(10);
-                    self.prev_token = Token::Empty;
-                    self.skip_whitespace();
-
-                    if self.chr == '.' {
+                match self.chr {
+                    '=' => {
                         self.next_char();
-                        let decimal = self.get_number(10);
-                        self.prev_token = Token::Empty;
-                        self.skip_whitespace();
-                        self.peek_tok = Token::Float(
-                            ("-".to_owned() + &num.to_string() + "." + &decimal.to_string())
-                                .parse()
-                                .unwrap(),
-                        );
-                        return out;
-                    }
-                    self.peek_tok = Token::Int(-1 * num);
-                    return out;
-                } else {
-                    match self.chr {
-                        '=' => {
-                            self.next_char();
-                            self.prev_token = Token::MinusEqual;
-                            self.skip_whitespace();
-                            self.peek_tok = Token::MinusEqual;
-                            return out;
-                        }
-                        '-' => {
-                            self.next_char();
-                            self.prev_token = Token::MinusMinus;
-                            self.skip_whitespace();
-                            self.peek_tok = Token::MinusMinus;
-                            return out;
-                        }
-                        _ => {
-                            self.prev_token = Token::Minus;
-                            self.skip_whitespace();
-                            self.peek_tok = Token::Minus;
-                            return out;
-                        }
+                        return Token::MinusEqual;
+                    }
+                    '-' => {
+                        self.next_char();
+                        return Token::MinusMinus;
+                    }
+                    _ => {
+                        return Token::Minus;
                     }
                 }
             }
@@ -387,126 +918,338 @@ impl Lexer {
             else if self.chr == '`' {
                 self.next_char();
                 let str = self.get_string();
-                self.peek_tok = Token::String(str);
-                return out;
+                return match str {
+                    Ok(str) => Token::String(Bytes(str.into_bytes())),
+                    Err(msg) => self.error(msg, Token::String(Bytes::default())),
+                };
             }
             // Strings
             else if self.chr == '"' {
                 self.next_char();
                 let str = self.get_interpreted_string();
-                self.next_char();
-                self.prev_token = Token::String("".to_string());
-                self.skip_whitespace();
-                self.peek_tok = Token::String(str);
-                return out;
+                return match str {
+                    Ok(bytes) => Token::String(bytes),
+                    Err(msg) => self.error(msg, Token::String(Bytes::default())),
+                };
             }
             // Rune
             else if self.chr == '\'' {
                 self.next_char();
-                if self.chr == '\\' {
-                    self.next_char();
-                    let chr = self.get_escape();
-                    if chr == '0' {
-                        self.peek_tok = Token::Error(chr.to_string(), self.line);
-                        return out;
+                let tok = self.get_rune();
+                return tok;
+            }
+            return self.get_operator();
+        }
+        // Error if ever gets here
+        let temp = self.chr;
+        let bad_utf8 = self.bad_utf8;
+        self.next_char();
+        if bad_utf8 {
+            return self.error("invalid UTF-8 encoding".to_string(), Token::Empty);
+        } else if temp == '\0' {
+            return self.error("invalid NUL character".to_string(), Token::Empty);
+        } else if temp == BOM {
+            return self.error("invalid BOM in the middle of the file".to_string(), Token::Empty);
+        }
+        return self.error(invalid_char(temp), Token::Empty);
+    }
+
+    fn get_ident(&mut self) -> &str {
+        let start = self.offset;
+        while (is_letter(self.chr) || is_digit(self.chr)) && !self.end {
+            self.next_char();
+        }
+        // Letters and digits are never malformed UTF-8, so the slice is valid
+        return std::str::from_utf8(&self.source[start..self.offset]).unwrap_or("");
+    }
+
+    // Scans an integer or floating-point literal. lit holds any part of the
+    // literal already consumed, which is "." for floats like .5. The whole
+    // literal is consumed even when it's malformed, and only the first error
+    // in it is reported
+    fn get_number(&mut self, mut lit: String) -> Token {
+        let mut base = 10;
+        let mut prefix = ' ';
+        let mut digits = 0;
+        let mut invalid_digit = None;
+        let mut float = false;
+        let mut err = None;
+
+        // Integer part
+        if lit.is_empty() {
+            if self.chr == '0' {
+                lit.push('0');
+                self.next_char();
+                match self.chr.to_ascii_lowercase() {
+                    'x' => base = 16,
+                    'o' => base = 8,
+                    'b' => base = 2,
+                    // Legacy octal like 0755, unless it turns out to be a float
+                    _ => {
+                        base = 8;
+                        prefix = '0';
                     }
+                }
+                if base != 8 || prefix != '0' {
+                    prefix = self.chr.to_ascii_lowercase();
+                    lit.push(self.chr);
                     self.next_char();
-                    self.prev_token = Token::Rune(' ');
-                    self.skip_whitespace();
-                    self.peek_tok = Token::Rune(chr);
-                    return out;
                 }
-                let temp = self.chr;
+            }
+            (digits, invalid_digit) = self.get_digits(&mut lit, base);
+        }
+
+        // Fractional part
+        if self.chr == '.' || lit == "." {
+            float = true;
+            if prefix == 'o' || prefix == 'b' {
+                err.get_or_insert(format!("invalid radix point in {}", literal_name(prefix)));
+            }
+            if self.chr == '.' {
+                lit.push('.');
                 self.next_char();
-                self.prev_token = Token::Rune(' ');
-                self.skip_whitespace();
-                self.peek_tok = Token::Rune(temp);
-                return out;
             }
-            self.prev_token = Token::Empty;
-            let tok = self.get_operator();
-            if let Token::Empty = tok {
-            } else {
-                self.peek_tok = tok;
+            digits += self.get_digits(&mut lit, if prefix == 'x' { 16 } else { 10 }).0;
+        }
+        if digits == 0 && prefix != ' ' && prefix != '0' {
+            err.get_or_insert(format!("{} has no digits", literal_name(prefix)));
+        }
+
+        // Exponent
+        let exp = self.chr.to_ascii_lowercase();
+        if exp == 'e' || exp == 'p' {
+            if exp == 'e' && prefix != ' ' && prefix != '0' {
+                err.get_or_insert("'e' exponent requires decimal mantissa".to_string());
+            } else if exp == 'p' && prefix != 'x' {
+                err.get_or_insert("'p' exponent requires hexadecimal mantissa".to_string());
             }
-            return out;
+            float = true;
+            lit.push(self.chr);
+            self.next_char();
+            if self.chr == '+' || self.chr == '-' {
+                lit.push(self.chr);
+                self.next_char();
+            }
+            let (digits, _) = self.get_digits(&mut lit, 10);
+            if digits == 0 {
+                err.get_or_insert("exponent has no digits".to_string());
+            }
+        } else if float && prefix == 'x' {
+            err.get_or_insert("hexadecimal mantissa requires a 'p' exponent".to_string());
         }
-        // EOF
-        else if self.chr == '\0' {
-            self.end = true;
-            self.peek_tok = Token::EOF;
-            return out;
+
+        // Token to recover with once the literal has been reported
+        let zero = if float { Token::Float(0.0) } else { Token::Int(0) };
+        if self.chr == 'i' {
+            self.next_char();
+            err.get_or_insert("imaginary literals are not supported".to_string());
         }
-        // whitespace
-        else if self.chr.is_whitespace() {
-            self.skip_whitespace();
-            return self.next_token();
+        if let Some(msg) = err {
+            return self.error(msg, zero);
+        }
+        if let Some(digit) = invalid_digit {
+            if !float {
+                return self.error(format!("invalid digit '{}' in {}", digit, literal_name(prefix)), Token::Int(0));
+            }
+        }
+        if invalid_separator(&lit) {
+            return self.error("'_' must separate successive digits".to_string(), zero);
+        }
+
+        let clean: String = lit.chars().filter(|c| *c != '_').collect();
+        if float {
+            let val = if prefix == 'x' { parse_hex_float(&clean[2..]) } else { clean.parse::<f64>().unwrap_or(f64::INFINITY) };
+            if val.is_infinite() {
+                return self.error(format!("floating-point literal {} overflows float64", lit), Token::Float(0.0));
+            }
+            return Token::Float(val);
+        }
+
+        // Strip the base prefix before converting
+        let digits = match prefix {
+            ' ' => &clean[..],
+            '0' => &clean[1..],
+            _ => &clean[2..],
+        };
+        if digits.is_empty() {
+            return Token::Int(0);
+        }
+        match i64::from_str_radix(digits, base) {
+            Ok(num) => return Token::Int(num),
+            Err(_) => return self.error(format!("integer literal {} overflows int", lit), Token::Int(0)),
         }
-        // Error if ever gets here
-        let temp = self.chr;
-        let line = self.line;
-        self.next_char();
-        self.peek_tok = Token::Error(temp.to_string(), line);
-        return out;
     }
 
-    fn get_ident(&mut self) -> String {
-        let mut str: String = String::from("");
-        while self.chr.is_alphanumeric() && !self.end {
-            str.push_str(&self.chr.to_string());
+    // Consumes digits and underscores into lit, returning how many digits were read
+    // and the first digit that isn't valid in the given base
+    fn get_digits(&mut self, lit: &mut String, base: u32) -> (usize, Option<char>) {
+        let mut digits = 0;
+        let mut invalid = None;
+        let max = if base <= 10 { 10 } else { base };
+        while (self.chr.is_digit(max) || self.chr == '_') && !self.end {
+            if self.chr != '_' {
+                digits += 1;
+                if !self.chr.is_digit(base) && invalid.is_none() {
+                    invalid = Some(self.chr);
+                }
+            }
+            lit.push(self.chr);
             self.next_char();
         }
-        return str;
+        return (digits, invalid);
     }
 
-    fn get_number(&mut self, radix: u32) -> i32 {
-        let mut num: i32 = 0;
-        while self.chr.is_digit(radix) && !self.end {
-            num = num * 10 + self.chr.to_digit(10).expect("Not a convertable number") as i32;
+    // Scans the rest of a raw string literal, including the closing quote.
+    // Carriage returns are dropped as the spec requires
+    fn get_string(&mut self) -> Result<String, String> {
+        let mut str = String::new();
+        let mut bad_utf8 = false;
+        while self.chr != '`' {
+            if self.end {
+                return Err("raw string literal not terminated".to_string());
+            }
+            bad_utf8 |= self.bad_utf8;
+            if self.chr != '\r' {
+                str.push(self.chr);
+            }
             self.next_char();
         }
-        return num;
+        self.next_char();
+        if bad_utf8 {
+            return Err("invalid UTF-8 encoding".to_string());
+        }
+        return Ok(str);
     }
 
-    fn get_string(&mut self) -> String {
-        let mut str = String::new();
+    // Scans the rest of an interpreted string literal, including the closing quote
+    fn get_interpreted_string(&mut self) -> Result<Bytes, String> {
+        // Byte escapes may spell out multi-byte characters, so collect raw bytes
+        let mut bytes = Vec::new();
+        let mut err = None;
         while self.chr != '"' {
-            str += &self.chr.to_string();
+            if self.chr == '\n' || self.end {
+                return Err(err.unwrap_or("string literal not terminated".to_string()));
+            }
+            if self.bad_utf8 {
+                err.get_or_insert("invalid UTF-8 encoding".to_string());
+            }
+            if self.chr == '\\' {
+                self.next_char();
+                match self.get_escape('"') {
+                    Ok((val, true)) => bytes.push(val as u8),
+                    Ok((val, false)) => {
+                        let chr = char::from_u32(val).unwrap_or(char::REPLACEMENT_CHARACTER);
+                        bytes.extend_from_slice(chr.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    Err(msg) => {
+                        err.get_or_insert(msg);
+                    }
+                }
+            } else {
+                bytes.extend_from_slice(self.chr.encode_utf8(&mut [0; 4]).as_bytes());
+                self.next_char();
+            }
         }
-        return str;
+        self.next_char();
+        if let Some(msg) = err {
+            return Err(msg);
+        }
+        return Ok(Bytes(bytes));
     }
 
-    fn get_interpreted_string(&mut self) -> String {
-        let mut str = String::new();
-        while self.chr != '"' {
+    // Scans the rest of a rune literal, including the closing quote
+    fn get_rune(&mut self) -> Token {
+        let mut count = 0;
+        let mut val = '\0';
+        let mut err = None;
+        while self.chr != '\'' {
+            if self.chr == '\n' || self.end {
+                let msg = err.unwrap_or("rune literal not terminated".to_string());
+                return self.error(msg, Token::Rune(val));
+            }
+            count += 1;
             if self.chr == '\\' {
                 self.next_char();
-                let mut chr = self.get_escape();
-                if chr == '0' {
-                    chr = ' ';
+                match self.get_escape('\'') {
+                    Ok((num, _)) => val = char::from_u32(num).unwrap_or(char::REPLACEMENT_CHARACTER),
+                    Err(msg) => {
+                        err.get_or_insert(msg);
+                    }
                 }
-                // ! Error but don't really know what to do
-                str += &chr.to_string();
             } else {
-                str += &self.chr.to_string();
+                if self.bad_utf8 {
+                    err.get_or_insert("invalid UTF-8 encoding".to_string());
+                }
+                val = self.chr;
+                self.next_char();
             }
-            self.next_char();
         }
-        return str;
+        self.next_char();
+
+        if let Some(msg) = err {
+            return self.error(msg, Token::Rune(val));
+        } else if count == 0 {
+            return self.error("empty rune literal or unescaped ' in rune literal".to_string(), Token::Rune(val));
+        } else if count > 1 {
+            return self.error("more than one character in rune literal".to_string(), Token::Rune(val));
+        }
+        return Token::Rune(val);
     }
 
-    fn get_escape(&mut self) -> char {
-        match self.chr {
-            'n' => return '\n',
-            '\\' => return '\\',
-            't' => return '\t',
-            'r' => return '\r',
-            '\'' => {
-                return '\'';
+    // Reads an escape sequence following its backslash. Returns the escaped value and
+    // whether it is a single byte (octal and \x escapes) rather than a code point
+    fn get_escape(&mut self, quote: char) -> Result<(u32, bool), String> {
+        let chr = self.chr;
+        let simple = match chr {
+            'a' => Some('\u{07}'),
+            'b' => Some('\u{08}'),
+            'f' => Some('\u{0C}'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'v' => Some('\u{0B}'),
+            '\\' => Some('\\'),
+            _ if chr == quote => Some(quote),
+            _ => None,
+        };
+        if let Some(val) = simple {
+            self.next_char();
+            return Ok((val as u32, false));
+        }
+
+        // Numeric escapes: how many digits, in what base, up to what value
+        let (len, base, max) = match chr {
+            '0'..='7' => (3, 8, 255),
+            'x' => (2, 16, 255),
+            'u' => (4, 16, 0x10FFFF),
+            'U' => (8, 16, 0x10FFFF),
+            '\n' => return Err("escape sequence not terminated".to_string()),
+            _ if self.end => return Err("escape sequence not terminated".to_string()),
+            _ => {
+                self.next_char();
+                return Err(format!("unknown escape sequence '\\{}'", chr));
+            }
+        };
+        if base == 16 {
+            self.next_char();
+        }
+        let mut val: u32 = 0;
+        for _ in 0..len {
+            match self.chr.to_digit(base) {
+                Some(digit) if !self.end => {
+                    val = val * base + digit;
+                    self.next_char();
+                }
+                // Leave the character alone, it may be the closing quote
+                _ => return Err(format!("illegal character {:?} in escape sequence", self.chr)),
             }
-            '"' => return '"',
-            _ => return '0',
         }
+        if base == 8 && val > max {
+            return Err(format!("octal escape value {} > 255", val));
+        } else if val > max || (max > 255 && (0xD800..0xE000).contains(&val)) {
+            return Err("escape sequence is invalid Unicode code point".to_string());
+        }
+        return Ok((val, max == 255));
     }
 
     fn get_operator(&mut self) -> Token {
@@ -516,16 +1259,13 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::PlusEqual;
                     }
                     '+' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::PlusPlus;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Plus;
                     }
                 }
@@ -535,11 +1275,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::TimesEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Aster;
                     }
                 }
@@ -549,21 +1287,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::DivEqual;
                     }
-                    '/' => {
-                        self.skip_comment();
-                        self.next_token();
-                        return Token::Empty;
-                    }
-                    '*' => {
-                        self.skip_multiline_comment();
-                        self.next_token();
-                        return Token::Empty;
-                    }
                     _ => {
-                        self.skip_whitespace();
                         return Token::FSlash;
                     }
                 }
@@ -576,7 +1302,6 @@ impl Lexer {
                         return Token::ModEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Percent;
                     }
                 }
@@ -588,24 +1313,19 @@ impl Lexer {
                         self.next_char();
                         if self.chr == '=' {
                             self.next_char();
-                            self.skip_whitespace();
                             return Token::AndNotEqual;
                         }
-                        self.skip_whitespace();
                         return Token::AndNot;
                     }
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::AndEqual;
                     }
                     '&' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::LogAnd;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Amper;
                     }
                 }
@@ -615,16 +1335,13 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::OrEqual;
                     }
                     '|' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::LogOr;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Or;
                     }
                 }
@@ -634,11 +1351,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::XorEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Xor;
                     }
                 }
@@ -650,19 +1365,19 @@ impl Lexer {
                         self.next_char();
                         if self.chr == '=' {
                             self.next_char();
-                            self.skip_whitespace();
                             return Token::LShiftEqual;
                         }
-                        self.skip_whitespace();
                         return Token::LShift;
                     }
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::LessEqual;
                     }
+                    '-' => {
+                        self.next_char();
+                        return Token::LArrow;
+                    }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Less;
                     }
                 }
@@ -674,19 +1389,15 @@ impl Lexer {
                         self.next_char();
                         if self.chr == '=' {
                             self.next_char();
-                            self.skip_whitespace();
                             return Token::RShiftEqual;
                         }
-                        self.skip_whitespace();
                         return Token::RShift;
                     }
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::GreaterEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Greater;
                     }
                 }
@@ -696,11 +1407,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::EqualEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Equal;
                     }
                 }
@@ -710,11 +1419,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::BangEqual;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Bang;
                     }
                 }
@@ -724,11 +1431,9 @@ impl Lexer {
                 match self.chr {
                     '=' => {
                         self.next_char();
-                        self.skip_whitespace();
                         return Token::Assignment;
                     }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Colon;
                     }
                 }
@@ -737,76 +1442,70 @@ impl Lexer {
                 self.next_char();
                 match self.chr {
                     '.' => {
-                        let line = self.line;
                         self.next_char();
                         if self.chr == '.' {
                             self.next_char();
-                            self.skip_whitespace();
                             return Token::Dots;
                         } else {
-                            return Token::Error("..".to_string(), line);
+                            return self.error("unexpected '..'".to_string(), Token::Empty);
                         }
                     }
+                    // Floats like .5
+                    '0'..='9' => {
+                        let tok = self.get_number(".".to_string());
+                        return tok;
+                    }
                     _ => {
-                        self.skip_whitespace();
                         return Token::Period;
                     }
                 }
             }
             ',' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::Comma;
             }
             '(' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::LParen;
             }
             ')' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::RParen;
             }
             '[' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::LBrack;
             }
             ']' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::RBrack;
             }
             '{' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::LBrace;
             }
             '}' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::RBrace;
             }
             ';' => {
                 self.next_char();
-                self.skip_whitespace();
                 return Token::Semicolon;
             }
             _ => {
                 let temp = self.chr;
-                let line = self.line;
                 self.next_char();
-                self.skip_whitespace();
-                return Token::Error(temp.to_string(), line);
+                return self.error(invalid_char(temp), Token::Empty);
             }
         }
     }
 
-    fn get_keyword(&mut self, str: &str) -> Token {
-        match str {
+    fn get_keyword(str: &str) -> Option<Token> {
+        let tok = match str {
             "break" => Token::Break,
             "case" => Token::Case,
+            "chan" => Token::Chan,
+            "const" => Token::Const,
             "continue" => Token::Continue,
             "default" => Token::Default,
             "defer" => Token::Defer,
@@ -830,40 +1529,14 @@ impl Lexer {
             "var" => Token::Var,
             "print" => Token::Print,
             "println" => Token::Println,
-            "append" => Token::Append,
-            "len" => Token::Len,
-            "cap" => Token::Cap,
-            "true" => Token::Bool(true),
-            "false" => Token::Bool(false),
-            _ => Token::Ident("".to_string()),
-        }
+            _ => return None,
+        };
+        return Some(tok);
     }
 
+    // Skips whitespace, stopping at a newline that ends a statement
     fn skip_whitespace(&mut self) {
-        while self.chr.is_whitespace() {
-            if self.chr == '\n' {
-                self.line += 1;
-                match self.prev_token {
-                    Token::Ident(_)
-                    | Token::Int(_)
-                    | Token::Float(_)
-                    | Token::Rune(_)
-                    | Token::String(_)
-                    | Token::Break
-                    | Token::Continue
-                    | Token::Fallthrough
-                    | Token::Return
-                    | Token::PlusPlus
-                    | Token::MinusMinus
-                    | Token::RParen
-                    | Token::RBrace
-                    | Token::RBrack => {
-                        self.chr = ';';
-                        return;
-                    }
-                    _ => {}
-                }
-            }
+        while self.chr.is_whitespace() && !(self.chr == '\n' && self.insert_semi) {
             self.next_char();
         }
     }
@@ -872,26 +1545,97 @@ impl Lexer {
         while self.chr != '\n' && !self.end {
             self.next_char();
         }
-        self.line += 1;
     }
 
-    fn skip_multiline_comment(&mut self) {
-        self.next_char();
-        let mut past = self.chr;
+    // Skips a general comment after its opening slash, returning whether it spanned
+    // multiple lines
+    fn skip_multiline_comment(&mut self) -> bool {
+        let mut newline = false;
         self.next_char();
-        while self.chr != '/' && past != '*' {
-            if self.chr == '\n' {
-                self.line += 1;
+        loop {
+            if self.end {
+                self.error("comment not terminated".to_string(), Token::Empty);
+                return newline;
             }
-            past = self.chr;
+            if self.chr == '*' {
+                self.next_char();
+                if self.chr == '/' {
+                    self.next_char();
+                    return newline;
+                }
+                continue;
+            }
+            newline |= self.chr == '\n';
             self.next_char();
         }
-        self.next_char();
     }
 
+    // Looks at the byte following the current character without consuming it
+    fn peek_byte(&self) -> Option<u8> {
+        return self.source.get(self.offset + self.width).copied();
+    }
+
+    // Decodes the next UTF-8 encoded character from the source
     fn next_char(&mut self) {
-        let mut buf = [0];
-        self.end = self.reader.read(&mut buf).expect("Couldn't read file") == 0;
-        self.chr = buf[0] as char;
+        // Move the position past the current character
+        self.offset += self.width;
+        if self.newline {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+
+        self.bad_utf8 = false;
+        self.newline = false;
+        let rest = &self.source[self.offset..];
+        if rest.is_empty() {
+            self.end = true;
+            self.width = 0;
+            self.chr = '\0';
+            return;
+        }
+
+        // ASCII needs no decoding
+        if rest[0] < 0x80 {
+            self.width = 1;
+            self.chr = rest[0] as char;
+            self.newline = self.chr == '\n';
+            return;
+        }
+
+        // The leading byte tells how many continuation bytes follow
+        let width = match rest[0] {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 1,
+        };
+        // Only take bytes that actually continue the sequence
+        let mut len = 1;
+        while len < width && len < rest.len() && rest[len] & 0xC0 == 0x80 {
+            len += 1;
+        }
+
+        self.width = len;
+        match std::str::from_utf8(&rest[..len]) {
+            Ok(str) => self.chr = str.chars().next().unwrap_or('\0'),
+            Err(_) => {
+                self.chr = char::REPLACEMENT_CHARACTER;
+                self.bad_utf8 = true;
+            }
+        }
+    }
+}
+
+// Yields the remaining tokens, stopping before EOF
+impl Iterator for Lexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.peek().same_type(&Token::EOF) {
+            return None;
+        }
+        return Some(self.next_token());
     }
 }
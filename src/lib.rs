@@ -0,0 +1,11 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+pub mod lexer;
+pub mod parser;
+pub mod ast;
+pub mod error;
+pub mod features;
+pub mod resolve;
+pub mod symbol;
+
+use lexer::Token;
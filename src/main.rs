@@ -1,21 +1,122 @@
-use std::{fs::File, io::BufReader};
+#![allow(clippy::needless_return)]
 
-use lexer::{Lexer, Token};
+use std::{env, process};
 
-pub mod lexer;
-pub mod parser;
-pub mod ast;
-pub mod error;
+use golite::error::Diagnostic;
+use golite::lexer::{Lexer, TriviaToken};
 
+const USAGE: &str = "usage: GoLite-Compiler tokens <file> [--json]
 
+Commands:
+  tokens, scan   Print every token in <file>, or stdin if <file> is -
+
+Output is one token per line:
+  <line>:<col> <kind> <lexeme>
+where <kind> is the token's name, such as Ident or Semicolon, and <lexeme> is
+its source text as a quoted string. Semicolons inserted at line ends have an
+empty lexeme. With --json, prints an object holding a \"tokens\" array and an
+\"errors\" array instead.
+
+Lexical errors go to stderr as <file>:<line>:<col>: <message>, and make the
+exit status 1.";
 
 fn main() {
-    let f = File::open("./test.go").expect("Couldn't find file");
-    let buf_reader = BufReader::new(f);
-    let mut lex = Lexer::new(buf_reader);
-    let mut tok = lex.next_token();
-    while !tok.same_type(&Token::EOF) {
-        println!("CURRENT: {:?}", tok);
-        tok = lex.next_token();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("tokens") | Some("scan") => tokens(&args[1..]),
+        Some("-h") | Some("--help") => println!("{}", USAGE),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn tokens(args: &[String]) {
+    let mut json = false;
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => usage(),
+        }
+    }
+
+    let (path, lex) = match path {
+        Some("-") => ("<stdin>", Lexer::from_stdin()),
+        Some(path) => (path, Lexer::from_file(path)),
+        None => usage(),
+    };
+    let lex = match lex {
+        Ok(lex) => lex,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(2);
+        }
+    };
+
+    let (toks, diags) = lex.tokenize_with_trivia();
+    if json {
+        println!("{}", render_json(&toks, &diags));
+    } else {
+        for tok in &toks {
+            println!("{}:{} {} {:?}", tok.span.line, tok.span.col, tok.tok.kind(), tok.text);
+        }
+    }
+
+    for diag in &diags {
+        eprintln!("{}", diag);
+    }
+    if !diags.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn render_json(toks: &[TriviaToken], diags: &[Diagnostic]) -> String {
+    let toks: Vec<String> = toks
+        .iter()
+        .map(|tok| {
+            format!(
+                "{{\"kind\":{},\"lexeme\":{},\"line\":{},\"col\":{},\"start\":{},\"end\":{}}}",
+                json_string(tok.tok.kind()),
+                json_string(&tok.text),
+                tok.span.line,
+                tok.span.col,
+                tok.span.start,
+                tok.span.end,
+            )
+        })
+        .collect();
+    let diags: Vec<String> = diags
+        .iter()
+        .map(|diag| {
+            format!(
+                "{{\"message\":{},\"line\":{},\"col\":{}}}",
+                json_string(&diag.msg),
+                diag.span.line,
+                diag.span.col,
+            )
+        })
+        .collect();
+    return format!("{{\"tokens\":[{}],\"errors\":[{}]}}", toks.join(","), diags.join(","));
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    return out;
 }
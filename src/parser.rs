@@ -1,126 +1,310 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::ast::ForClause;
 use crate::ast::ParamList;
 use crate::error;
+use crate::features;
 use crate::lexer::Lexer;
+use crate::symbol::Symbol;
 
 use super::ast;
 use super::Token;
 
 pub fn parse(lex: &mut Lexer) -> ast::AST {
-    let mut package: String;
-    let mut main: ast::Declaration;
-    let mut func_env: HashMap<String, ast::FuncLiteral> = HashMap::new();
-    let mut var_env: HashMap<String, ast::> = HashMap::new();
-    let mut type_env: HashMap<String, ast::Type> = HashMap::new();
+    // Lexical errors and Go constructs outside GoLite would only confuse the
+    // parser, so report them all before starting. Checking for unsupported
+    // features lexes the whole file, so every lexical error is known by then
+    let mut diags = features::check_lexer(lex);
+    diags.extend(lex.diagnostics().iter().cloned());
+    if !diags.is_empty() {
+        diags.sort_by_key(|diag| diag.span.start);
+        error::diagnostics_err(&diags);
+    }
 
     // Find the package
     lex.eat(&Token::Package);
-    if let Token::Ident(name) = lex.next_token() {
-        package = name;
-    }
+    let package = parse_ident(lex);
     lex.eat(&Token::Semicolon);
 
-    let mut tok = lex.next_token();
-
-    // Parse whole file
-    while tok != Token::EOF {
-        match tok {
-            Token::Const => todo!(),
-            Token::Func => {
-                parse_func(lex, &mut func_env);
-            }
+    // Parse whole file, keeping the declarations in source order
+    let mut decls = Vec::new();
+    let mut method_table: BTreeMap<Symbol, Vec<usize>> = BTreeMap::new();
+    while !lex.peek().same_type(&Token::EOF) {
+        match lex.peek() {
             Token::Import => {
-                while !tok.same_type(&Token::Semicolon) {
-                    tok = lex.next_token();
+                lex.eat(&Token::Import);
+                parse_imports(lex, &mut decls);
+            }
+            Token::Func => {
+                lex.eat(&Token::Func);
+                if lex.peek().same_type(&Token::LParen) {
+                    let recv = parse_receiver(lex);
+                    let (name, func) = parse_func(lex);
+                    method_table.entry(recv.base).or_default().push(decls.len());
+                    decls.push(ast::TopLevelDecl::Method(ast::Method { recv, name, func }));
+                } else {
+                    let (name, func) = parse_func(lex);
+                    decls.push(ast::TopLevelDecl::Func(name, func));
                 }
             }
-            
+
             // Type Declaration
             Token::Type => {
-                
-            },
+                decls.push(ast::TopLevelDecl::Decl(parse_type_decl(lex)));
+            }
             // Var Declaration
             Token::Var => {
-
-            },
-            _ => error::token_type_err(&tok, &Token::Func )
+                decls.push(ast::TopLevelDecl::Decl(parse_var_decl(lex)));
+            }
+            _ => {
+                error::token_type_err(lex.peek(), &Token::Func, lex.peek_span());
+            }
         }
+        lex.eat(&Token::Semicolon);
     }
 
-    todo!();
+    return ast::AST { package, decls, method_table };
 }
 
+// (s *Stack), (s Stack), (*Stack), or (Stack)
+fn parse_receiver(lex: &mut Lexer) -> ast::Receiver {
+    lex.eat(&Token::LParen);
 
-fn parse_func(lex: &mut Lexer, func_env: &mut HashMap<String, ast::FuncLiteral>) {
-    // Get the func name
-    let mut tok = lex.next_token();
-    let func_name: String;
+    // A name is followed by the type, where a lone type is followed by )
+    let mut name = None;
+    if lex.peek().same_type(&Token::BlankIdent) {
+        lex.eat(&Token::BlankIdent);
+    } else if lex.peek().is_ident() && !lex.peek_nth(1).same_type(&Token::RParen) {
+        name = Some(parse_ident(lex));
+    }
 
-    if let Token::Ident(name) = tok {
-        func_name = name;
-    } else {
-        error::token_type_err(&tok, &Token::Ident("".to_string()));
-        todo!();
+    let pointer = lex.peek().same_type(&Token::Aster);
+    if pointer {
+        lex.eat(&Token::Aster);
     }
-    lex.eat(&Token::LParen);
+    let base = parse_ident(lex);
+    lex.eat(&Token::RParen);
+    return ast::Receiver { name, base, pointer };
+}
 
-    let mut param_list: ast::ParamList = ParamList { params: Vec::new() };
+// Import specs, either one or a parenthesized group
+fn parse_imports(lex: &mut Lexer, decls: &mut Vec<ast::TopLevelDecl>) {
+    if !lex.peek().same_type(&Token::LParen) {
+        decls.push(parse_import_spec(lex));
+        return;
+    }
 
-    // Get the func parameters
+    lex.eat(&Token::LParen);
     while !lex.peek().same_type(&Token::RParen) {
-        tok = lex.next_token();
-        let mut param_names = Vec::new();
-        if let Token::Ident(name) = tok {
-            param_names.push(name);
-        } else {
-            error::token_type_err(&tok, &Token::Ident("".to_string()));
-        }
+        decls.push(parse_import_spec(lex));
 
-        // If there are multiple names
-        while lex.peek().same_type(&Token::Comma) {
-            lex.next_token();
-            tok = lex.next_token();
-            if let Token::Ident(name) = tok {
-                param_names.push(name);
-            } else {
-                error::token_type_err(&tok, &Token::Ident("".to_string()));
-            }
+        // The last one doesn't need a semicolon
+        if !lex.peek().same_type(&Token::RParen) {
+            lex.eat(&Token::Semicolon);
         }
+    }
+    lex.eat(&Token::RParen);
+}
 
-        //The type of the parameters
-        param_list.params.push((param_names, parse_type(lex)));
-        if lex.peek().same_type(&Token::Comma) {
-            lex.next_token();
+// An import path, optionally preceded by the name to import it as, _, or .
+fn parse_import_spec(lex: &mut Lexer) -> ast::TopLevelDecl {
+    let alias = match lex.peek() {
+        Token::Ident(name) => Some(*name),
+        Token::BlankIdent => Some(Symbol::intern("_")),
+        Token::Period => Some(Symbol::intern(".")),
+        _ => None,
+    };
+    if alias.is_some() {
+        lex.next_token();
+    }
+    return ast::TopLevelDecl::Import(alias, parse_import_path(lex));
+}
+
+fn parse_import_path(lex: &mut Lexer) -> String {
+    let tok = lex.next_token();
+    if let Token::String(path) = &tok {
+        if let Some(path) = path.as_str() {
+            return path.to_string();
         }
     }
+    error::expected_err(&tok, "import path", lex.span());
+}
 
-    lex.eat(&Token::RParen);
+fn parse_func(lex: &mut Lexer) -> (Symbol, ast::FuncLiteral) {
+    // Get the func name
+    let func_name = parse_ident(lex);
 
-    // Get return type if there is one
-    let ret: Option<ast::Type>;
+    // Get the func parameters
+    let param_list = parse_params(lex);
 
-    if !(lex.peek().same_type(&Token::Ident("".to_string()))
-        || lex.peek().same_type(&Token::RBrack))
-    {
+    // Get the results if there are any: a list like the parameters, or a lone type
+    let ret: Option<ParamList>;
+    if lex.peek().same_type(&Token::LBrace) {
         ret = None;
+    } else if lex.peek().same_type(&Token::LParen) {
+        ret = Some(parse_params(lex));
     } else {
-        ret = Some(parse_type(lex));
+        ret = Some(ParamList { params: vec![(Vec::new(), parse_type(lex))] });
     }
 
     // Parse the block/StmtList
+    let body = parse_block(lex);
+
     let lit = ast::FuncLiteral {
         params: if param_list.params.is_empty() {
             None
         } else {
             Some(param_list)
         },
-        ret,
-        body: parse_stmt_list(lex),
+        ret: ret.filter(|list| !list.params.is_empty()),
+        body,
     };
 
-    func_env.insert(func_name, lit);
+    return (func_name, lit);
+}
+
+// A parenthesized parameter or result list. Either every entry is named, as
+// in (a, b int, s string), or none are, as in (int, string). A lone name
+// can't tell which until the whole list is read. A name can be _, as in
+// (_ int, err bool), but a type can't
+fn parse_params(lex: &mut Lexer) -> ParamList {
+    lex.eat(&Token::LParen);
+    let mut entries: Vec<(Option<Symbol>, Option<ast::Type>)> = Vec::new();
+    while !lex.peek().same_type(&Token::RParen) {
+        let name = match lex.peek() {
+            Token::Ident(name) => Some(*name),
+            Token::BlankIdent => Some(Symbol::intern("_")),
+            _ => None,
+        };
+        if let Some(name) = name {
+            lex.next_token();
+            if lex.peek().same_type(&Token::Comma) || lex.peek().same_type(&Token::RParen) {
+                entries.push((Some(name), None));
+            } else {
+                entries.push((Some(name), Some(parse_type(lex))));
+            }
+        } else {
+            entries.push((None, Some(parse_type(lex))));
+        }
+
+        // The last one doesn't need a comma
+        if !lex.peek().same_type(&Token::RParen) {
+            lex.eat(&Token::Comma);
+        }
+    }
+    lex.eat(&Token::RParen);
+
+    let mut params = Vec::new();
+    let named = entries.iter().any(|(name, param_type)| name.is_some() && param_type.is_some());
+    if !named {
+        // Every name was really a type
+        for (name, param_type) in entries {
+            let param_type = match (param_type, name) {
+                (Some(param_type), _) => param_type,
+                (None, Some(name)) if name != "_" => ast::Type::Named(name),
+                _ => error::ast_type_err("_", "parameter type", lex.span()),
+            };
+            params.push((Vec::new(), param_type));
+        }
+        return ParamList { params };
+    }
+
+    // Names without a type share the next one's
+    let mut names = Vec::new();
+    for (name, param_type) in entries {
+        match name {
+            Some(name) => names.push(name),
+            None => error::ast_type_err("type", "named parameter", lex.span()),
+        }
+        if let Some(param_type) = param_type {
+            params.push((names, param_type));
+            names = Vec::new();
+        }
+    }
+    if !names.is_empty() {
+        error::ast_type_err(")", "parameter type", lex.span());
+    }
+    return ParamList { params };
+}
+
+// A type declaration, either one or a parenthesized group
+fn parse_type_decl(lex: &mut Lexer) -> ast::Declaration {
+    lex.eat(&Token::Type);
+
+    // Single
+    if !lex.peek().same_type(&Token::LParen) {
+        let ident = parse_ident(lex);
+        return ast::Declaration::Type(vec![(ident, parse_type(lex))]);
+    }
+
+    // Multiple
+    lex.eat(&Token::LParen);
+    let mut decls = Vec::new();
+    while !lex.peek().same_type(&Token::RParen) {
+        let ident = parse_ident(lex);
+        decls.push((ident, parse_type(lex)));
+
+        // The last one doesn't need a semicolon
+        if !lex.peek().same_type(&Token::RParen) {
+            lex.eat(&Token::Semicolon);
+        }
+    }
+    lex.eat(&Token::RParen);
+    return ast::Declaration::Type(decls);
+}
+
+// A var declaration, either one spec or a parenthesized group
+fn parse_var_decl(lex: &mut Lexer) -> ast::Declaration {
+    lex.eat(&Token::Var);
+
+    // Single
+    if !lex.peek().same_type(&Token::LParen) {
+        return ast::Declaration::Var(vec![parse_var_spec(lex)]);
+    }
+
+    // Multiple
+    lex.eat(&Token::LParen);
+    let mut specs = Vec::new();
+    while !lex.peek().same_type(&Token::RParen) {
+        specs.push(parse_var_spec(lex));
+
+        // The last one doesn't need a semicolon
+        if !lex.peek().same_type(&Token::RParen) {
+            lex.eat(&Token::Semicolon);
+        }
+    }
+    lex.eat(&Token::RParen);
+    return ast::Declaration::Var(specs);
+}
+
+// Identifiers, then a type, an initializer, or both
+fn parse_var_spec(lex: &mut Lexer) -> ast::VarSpec {
+    let mut ident_list = vec![parse_ident(lex)];
+    while lex.peek().same_type(&Token::Comma) {
+        lex.eat(&Token::Comma);
+        ident_list.push(parse_ident(lex));
+    }
+
+    let mut var_type = None;
+    if !lex.peek().same_type(&Token::Equal) {
+        var_type = Some(parse_type(lex));
+    }
+
+    let mut expr_list = None;
+    if lex.peek().same_type(&Token::Equal) {
+        lex.eat(&Token::Equal);
+        expr_list = Some(parse_expr_list(lex, None, false));
+    }
+
+    return ast::VarSpec { ident_list, var_type, expr_list };
+}
+
+// Consumes an identifier and returns its name
+fn parse_ident(lex: &mut Lexer) -> Symbol {
+    let tok = lex.next_token();
+    if let Token::Ident(name) = tok {
+        return name;
+    }
+    error::expected_err(&tok, "identifier", lex.span());
 }
 
 fn parse_type(lex: &mut Lexer) -> ast::Type {
@@ -134,28 +318,21 @@ fn parse_type(lex: &mut Lexer) -> ast::Type {
         }
 
         // Types
+        // Even the predeclared ones like int can be shadowed, so every name
+        // is left for the resolver to look up
         Token::Ident(name) => {
-            match name as &str {
-                "int" => {
-                    return ast::Type::Integer;
-                }
-                "float" => {
-                    return ast::Type::Float;
-                }
-                "rune" => {
-                    return ast::Type::Rune;
-                }
-                "bool" => {
-                    return ast::Type::Bool;
-                }
-                "string" => {
-                    return ast::Type::String;
-                }
-                _ => {
-                    // TODO STRUCT
-                    todo!();
-                }
-            }
+            let name = *name;
+            lex.next_token();
+            return ast::Type::Named(name);
+        }
+
+        Token::Struct => {
+            return parse_struct_type(lex);
+        }
+
+        Token::Aster => {
+            lex.eat(&Token::Aster);
+            return ast::Type::Pointer(Box::from(parse_type(lex)));
         }
 
         // Arrays or Slices
@@ -170,107 +347,140 @@ fn parse_type(lex: &mut Lexer) -> ast::Type {
 
                 // Array
                 Token::Int(num) => {
-                    let temp = num.clone();
+                    let temp = *num;
                     lex.next_token();
+                    lex.eat(&Token::RBrack);
                     return ast::Type::Array(temp, Box::from(parse_type(lex)));
                 }
                 _ => {
-                    error::token_type_err(lex.peek(), &Token::RBrack);
-                    return ast::Type::Bool;
+                    error::token_type_err(lex.peek(), &Token::RBrack, lex.peek_span());
                 }
             }
         }
         _ => {
-            error::token_type_err(lex.peek(), &Token::Ident("".to_string()));
-            return ast::Type::Bool;
+            error::expected_err(lex.peek(), "identifier", lex.peek_span());
         }
     }
 }
 
+// struct { x, y int; next []point }
+fn parse_struct_type(lex: &mut Lexer) -> ast::Type {
+    lex.eat(&Token::Struct);
+    lex.eat(&Token::LBrace);
+
+    let mut fields: Vec<(Symbol, ast::Type)> = Vec::new();
+    while !lex.peek().same_type(&Token::RBrace) {
+        // Get the names sharing this type
+        let mut names = Vec::new();
+        loop {
+            let name;
+            if lex.peek().same_type(&Token::BlankIdent) {
+                lex.eat(&Token::BlankIdent);
+                name = Symbol::intern("_");
+            } else {
+                name = parse_ident(lex);
+            }
+
+            // Blank fields are only padding, so there can be any number of them
+            let taken = fields.iter().any(|(field, _)| *field == name) || names.contains(&name);
+            if name != "_" && taken {
+                error::duplicate_err("field", name.as_str(), lex.span());
+            }
+            names.push(name);
+
+            if !lex.peek().same_type(&Token::Comma) {
+                break;
+            }
+            lex.eat(&Token::Comma);
+        }
+
+        let field_type = parse_type(lex);
+        for name in names {
+            fields.push((name, field_type.clone()));
+        }
+
+        // The last one doesn't need a semicolon
+        if !lex.peek().same_type(&Token::RBrace) {
+            lex.eat(&Token::Semicolon);
+        }
+    }
+    lex.eat(&Token::RBrace);
+    return ast::Type::Struct(fields);
+}
+
 fn parse_stmt_list(lex: &mut Lexer) -> ast::StmtList {
     let mut stmts = Vec::new();
-    while !lex.peek().same_type(&Token::RBrace) {
-        let stmt = parse_stmt(lex);
-        lex.eat(&Token::Semicolon);
-        stmts.push(stmt);
+    while !ends_stmt_list(lex.peek()) {
+        stmts.push(parse_stmt(lex));
+
+        // The last one doesn't need a semicolon
+        if !ends_stmt_list(lex.peek()) {
+            lex.eat(&Token::Semicolon);
+        }
     }
     return ast::StmtList { stmts };
 }
 
+// A block ends at its closing brace, and a case clause at the next clause
+fn ends_stmt_list(tok: &Token) -> bool {
+    return tok.same_type(&Token::RBrace) || tok.same_type(&Token::Case) || tok.same_type(&Token::Default);
+}
+
+// { StmtList }
+fn parse_block(lex: &mut Lexer) -> ast::StmtList {
+    lex.eat(&Token::LBrace);
+    let block = parse_stmt_list(lex);
+    lex.eat(&Token::RBrace);
+    return block;
+}
+
 fn parse_stmt(lex: &mut Lexer) -> ast::Statement {
     match lex.peek() {
-        Token::Print => {
-            lex.eat(&Token::LParen);
-            let stmt;
-            if let Token::RParen = lex.peek() {
-                stmt = ast::Statement::PrintStmt(ast::PrintType::Print, None);
+        Token::Print | Token::Println => {
+            let print_type = if lex.next_token().same_type(&Token::Print) {
+                ast::PrintType::Print
             } else {
-                stmt = ast::Statement::PrintStmt(
-                    ast::PrintType::Print,
-                    Some(parse_expr_list(lex, None)),
-                );
-            }
-            lex.eat(&Token::RParen);
-            lex.eat(&Token::Semicolon);
-            return stmt;
-        }
-        Token::Println => {
+                ast::PrintType::Println
+            };
             lex.eat(&Token::LParen);
-            let stmt;
-            if let Token::RParen = lex.peek() {
-                stmt = ast::Statement::PrintStmt(ast::PrintType::Println, None);
-            } else {
-                stmt = ast::Statement::PrintStmt(
-                    ast::PrintType::Println,
-                    Some(parse_expr_list(lex, None)),
-                );
+            let mut args = None;
+            if !lex.peek().same_type(&Token::RParen) {
+                args = Some(parse_expr_list(lex, None, false));
             }
             lex.eat(&Token::RParen);
-            lex.eat(&Token::Semicolon);
-            return stmt;
+            return ast::Statement::PrintStmt(print_type, args);
         }
         Token::Return => {
-            lex.next_token();
-            // TODO check if it's an expr
-            if let Token::Semicolon = lex.peek() {
-                lex.next_token();
+            lex.eat(&Token::Return);
+            if lex.peek().same_type(&Token::Semicolon) || lex.peek().same_type(&Token::RBrace) {
                 return ast::Statement::ReturnStmt(None);
             } else {
-                return ast::Statement::ReturnStmt(Some(parse_expr(lex)));
+                return ast::Statement::ReturnStmt(Some(parse_expr_list(lex, None, false)));
             }
         }
         Token::Break => {
-            lex.eat(&Token::Semicolon);
+            lex.eat(&Token::Break);
             return ast::Statement::BreakStmt;
         }
         Token::Continue => {
-            lex.eat(&Token::Semicolon);
+            lex.eat(&Token::Continue);
             return ast::Statement::ContinueStmt;
         }
         Token::Fallthrough => {
-            lex.eat(&Token::Semicolon);
+            lex.eat(&Token::Fallthrough);
             return ast::Statement::FallthroughStmt;
         }
+        Token::LBrace => {
+            return ast::Statement::StmtList(Box::new(parse_block(lex)));
+        }
         Token::If => {
-            lex.next_token();
-            let mut simple = Option::from(parse_simple_stmt(lex));
-            let expr: ast::Expression;
-
-            // Check if it is a simple stmt or just an expr
-            if !lex.peek().same_type(&Token::Semicolon) {
-                if let ast::SimpleStmt::ExprStmt(temp) = simple.unwrap() {
-                    expr = temp;
-                } else {
-                    error::ast_type_err("IDK Something else", "Expression");
-                    todo!();
-                }
-                simple = None;
-            } else {
-                expr = parse_expr(lex);
-            }
-            lex.eat(&Token::LBrace);
-            let block = parse_stmt_list(lex);
-            lex.eat(&Token::RBrace);
+            lex.eat(&Token::If);
+            let (simple, expr) = parse_header(lex);
+            let expr = match expr {
+                Some(expr) => expr,
+                None => error::ast_type_err("nothing", "Expression", lex.peek_span()),
+            };
+            let block = parse_block(lex);
 
             if lex.peek().same_type(&Token::Else) {
                 lex.next_token();
@@ -278,7 +488,7 @@ fn parse_stmt(lex: &mut Lexer) -> ast::Statement {
                 if lex.peek().same_type(&Token::If) {
                     else_stmt = parse_stmt(lex);
                 } else {
-                    else_stmt = ast::Statement::StmtList(Box::new(parse_stmt_list(lex)));
+                    else_stmt = ast::Statement::StmtList(Box::new(parse_block(lex)));
                 }
                 return ast::Statement::IfStmt(
                     simple,
@@ -291,30 +501,8 @@ fn parse_stmt(lex: &mut Lexer) -> ast::Statement {
             }
         }
         Token::Switch => {
-            lex.next_token();
-
-            // Check if there's a simple stmt or expressoin
-            let simple;
-            let simple_final;
-            let expr;
-            if lex.peek().same_type(&Token::LBrace) {
-                simple_final = None;
-                expr = None;
-            } else {
-                simple = Some(parse_simple_stmt(lex));
-                if lex.peek().same_type(&Token::Semicolon) {
-                    simple_final = simple;
-                    expr = Some(parse_expr(lex));
-                } else {
-                    if let ast::SimpleStmt::ExprStmt(expr2) = simple.unwrap() {
-                        expr = Some(expr2);
-                        simple_final = None;
-                    } else {
-                        error::ast_type_err("IDK Something else", "Expression");
-                        todo!();
-                    }
-                }
-            }
+            lex.eat(&Token::Switch);
+            let (simple, expr) = parse_header(lex);
             lex.eat(&Token::LBrace);
             let mut cases = Vec::new();
 
@@ -332,7 +520,7 @@ fn parse_stmt(lex: &mut Lexer) -> ast::Statement {
                 // A case
                 else {
                     lex.eat(&Token::Case);
-                    let expr_list = parse_expr_list(lex, None);
+                    let expr_list = parse_expr_list(lex, None, false);
                     lex.eat(&Token::Colon);
                     cases.push(ast::ExprCaseClause {
                         expr: Some(expr_list),
@@ -341,555 +529,507 @@ fn parse_stmt(lex: &mut Lexer) -> ast::Statement {
                 }
             }
             lex.eat(&Token::RBrace);
-            return ast::Statement::SwitchStmt(simple_final, expr, cases);
+            return ast::Statement::SwitchStmt(simple, expr, cases);
         }
         Token::For => {
             lex.eat(&Token::For);
 
             // Check if it's an infinite for
             if lex.peek().same_type(&Token::LBrace) {
-                lex.eat(&Token::LBrace);
-                let stmt = parse_stmt_list(lex);
-                lex.eat(&Token::RBrace);
-                return ast::Statement::ForStmt(None, stmt);
+                return ast::Statement::ForStmt(None, parse_block(lex));
             }
 
-            let for_clause: ForClause;
-            let simple = parse_simple_stmt(lex);
-
-            // Determine if it's an expr or a clause
-            if lex.peek().same_type(&Token::Semicolon) {
-                lex.eat(&Token::Semicolon);
-
-                // whether the Expr is empty
-                if lex.peek().same_type(&Token::Semicolon) {
-                    lex.eat(&Token::Semicolon);
-
-                    // whether the second statment is empty
-                    if lex.peek().same_type(&Token::LBrace) {
-                        // Some, Empty, Empty
-                        for_clause = ast::ForClause {
-                            stmt1: Some(simple),
-                            expr: None,
-                            stmt2: None,
-                        }
-                    } else {
-                        //Some, Empty, Some
-                        for_clause = ast::ForClause {
-                            stmt1: Some(simple),
-                            expr: None,
-                            stmt2: Some(parse_simple_stmt(lex))
-                        }
-                    }
-                } else {
-                    let expr = parse_expr(lex);
-
-                    lex.eat(&Token::Semicolon);
-
-                    // Check if third statement is empty
-                    if lex.peek().same_type(&Token::RBrace) {
-                        // Some, Some, Empty
-                        for_clause = ast::ForClause{
-                            stmt1: Some(simple),
-                            expr: Some(expr),
-                            stmt2: None,
-                        }
-                    } 
-                    // Some, Some, Some
-                    else {
-                        for_clause = ast::ForClause {
-                            stmt1: Some(simple),
-                            expr: Some(expr),
-                            stmt2: Some(parse_simple_stmt(lex)),
-                        }
-                    }
-                }
-            } else {
-                // If there is only an expression
-                if let ast::SimpleStmt::ExprStmt(expr) = simple {
-                    for_clause = ast::ForClause {
-                        stmt1: None,
-                        expr: Some(expr),
-                        stmt2: None,
-                    };
-                } else {
-                    error::ast_type_err("IDK SOMETHING NOT RIGHT", "Expression");
-                    todo!();
-                }
+            if let Some(range_clause) = parse_range_clause(lex) {
+                return ast::Statement::RangeStmt(range_clause, parse_block(lex));
             }
-            
-            // Parse the block
-            lex.eat(&Token::LBrace);
 
-            let block = parse_stmt_list(lex);
-            
-            lex.eat(&Token::RBrace);
-            return ast::Statement::ForStmt(Some(for_clause), block);
+            let for_clause = parse_for_clause(lex);
+            return ast::Statement::ForStmt(Some(for_clause), parse_block(lex));
         }
         Token::Type => {
-            lex.eat(&Token::Type);
-
-            // Single or multiple type defs
-            if lex.peek().same_type(&Token::LParen) {
-                // Multiple
-                let mut decls = Vec::new();
-                while !lex.peek().same_type(&Token::RParen) {
-                    // Get the name
-                    let mut ident = "".to_string();
-                    if let Token::Ident(name) = lex.peek() {
-                        ident = name.to_string();
-                    } else {
-                        error::token_type_err(lex.peek(), &Token::Ident("".to_string()));
-                    }
-                    lex.next_token();
-
-                    // Get the decl and add to vec
-                    decls.push((ident, parse_type(lex)));
-                }
-                return ast::Statement::Decl(ast::Declaration::Type(decls));
-
-            } else if lex.peek().same_type(&Token::Ident("".to_string())) {
-                // Single
-                // Get name
-                let mut ident = "".to_string();
-                if let Token::Ident(name) = lex.next_token() {
-                    ident = name;
-                }
-
-                // Get Type
-                return ast::Statement::Decl(ast::Declaration::Type(vec![(ident, parse_type(lex))]));
-                
-            } else {
-                error::token_type_err(lex.peek(), &Token::Ident("".to_string()));
-                // TODO Maybe add an error thing
-                todo!();
-            }
+            return ast::Statement::Decl(parse_type_decl(lex));
         }
         Token::Var => {
-            lex.eat(&Token::Var);
-
-            // One of multiple vars
-            if lex.peek().same_type(&Token::LParen) {
-                // Multiple
-                lex.next_token();
-                
-                let mut specs = Vec::new();
+            return ast::Statement::Decl(parse_var_decl(lex));
+        }
+        _ => {
+            return ast::Statement::SimpleStmt(parse_simple_stmt(lex, false));
+        }
+    }
+}
 
-                // Get all the vars
-                while !lex.peek().same_type(&Token::RParen) {
+// The header of an if or switch: an optional simple statement and semicolon,
+// then an optional expression
+fn parse_header(lex: &mut Lexer) -> (Option<ast::SimpleStmt>, Option<ast::Expression>) {
+    if lex.peek().same_type(&Token::LBrace) {
+        return (None, None);
+    }
 
-                    // Get the ident list
-                    let mut ident_list = Vec::new();
-                    while let Token::Ident(name) = lex.peek() {
-                        ident_list.push(name.to_string());
-                        lex.next_token();
-                    }
-                    
-                    // Get the type
-                    let var_type = parse_type(lex);
-
-                    // Potentially get the expr list
-                    if lex.peek().same_type(&Token::Equal) {
-                        lex.eat(&Token::Equal);
-                        specs.push(ast::VarSpec{
-                            ident_list,
-                            var_type,
-                            expr_list: Some(parse_expr_list(lex, None)),
-                        })
-                    } else {
-                        specs.push(ast::VarSpec{
-                            ident_list,
-                            var_type,
-                            expr_list: None,
-                        })
-                    }
-                    lex.eat(&Token::Semicolon);
-                }
+    let simple = parse_simple_stmt(lex, true);
+    if lex.peek().same_type(&Token::Semicolon) {
+        lex.eat(&Token::Semicolon);
+        if lex.peek().same_type(&Token::LBrace) {
+            return (Some(simple), None);
+        }
+        return (Some(simple), Some(parse_expr(lex, true)));
+    }
 
-                lex.eat(&Token::RParen);
-                return ast::Statement::Decl(ast::Declaration::Var(specs));
+    // Just an expression
+    if let ast::SimpleStmt::ExprStmt(expr) = simple {
+        return (None, Some(expr));
+    }
+    error::ast_type_err("SimpleStmt", "Expression", lex.peek_span());
+}
 
-            } else {
-                // One
-                
-                // Identifiers
-                let mut ident_list = Vec::new();
-                while let Token::Ident(name) = lex.peek() {
-                    ident_list.push(name.to_string());
-                    lex.next_token();
-                }
+// [key [, value] (:= | =)] range expr. Gives back None without consuming
+// anything if the header is some other kind
+fn parse_range_clause(lex: &mut Lexer) -> Option<ast::RangeClause> {
+    if lex.peek().same_type(&Token::Range) {
+        lex.eat(&Token::Range);
+        return Some(ast::RangeClause { key: None, value: None, define: false, expr: parse_expr(lex, true) });
+    }
+    if lex.peek().same_type(&Token::Semicolon) {
+        return None;
+    }
 
-                // Type
-                let var_type = parse_type(lex);
+    // Read ahead to see whether range follows the assignment
+    let start = lex.checkpoint();
+    let mut lhs = parse_expr_list(lex, None, true).exprs;
+    let define = lex.peek().same_type(&Token::Assignment);
+    if !((define || lex.peek().same_type(&Token::Equal)) && lex.peek_nth(1).same_type(&Token::Range)) {
+        lex.rewind(start);
+        return None;
+    }
+    lex.next_token();
+    lex.eat(&Token::Range);
 
-                // Potential exprList
-                if lex.peek().same_type(&Token::Equal) {
-                    return ast::Statement::Decl(ast::Declaration::Var(vec![ast::VarSpec{
-                        ident_list,
-                        var_type,
-                        expr_list: Some(parse_expr_list(lex, None)),
-                    }]))
-                } else {
-                    return ast::Statement::Decl(ast::Declaration::Var(vec![ast::VarSpec{
-                        ident_list,
-                        var_type,
-                        expr_list: None,
-                    }]))
+    if lhs.len() > 2 {
+        error::ast_type_err("more than two expressions", "at most key and value", lex.span());
+    }
+    // := declares new variables, so it only takes names
+    if define {
+        for expr in &lhs {
+            let is_name = match expr {
+                ast::Expression::PrimaryExpr(prim) => {
+                    matches!(prim.as_ref(), ast::PrimaryExpr::Operand(ast::Operand::Op(_)))
                 }
-            } 
+                _ => false,
+            };
+            if !is_name {
+                error::ast_type_err("Expression", "identifier", lex.span());
+            }
         }
-        _ => {
-            return ast::Statement::SimpleStmt(parse_simple_stmt(lex));
+    }
+
+    let value = if lhs.len() == 2 { lhs.pop() } else { None };
+    let key = lhs.pop();
+    return Some(ast::RangeClause { key, value, define, expr: parse_expr(lex, true) });
+}
+
+// Either a condition or init; condition; post, any of them empty
+fn parse_for_clause(lex: &mut Lexer) -> ast::ForClause {
+    let simple = parse_simple_stmt(lex, true);
+
+    // If there is only an expression
+    if !lex.peek().same_type(&Token::Semicolon) {
+        if let ast::SimpleStmt::ExprStmt(expr) = simple {
+            return ast::ForClause {
+                stmt1: None,
+                expr: Some(expr),
+                stmt2: None,
+            };
         }
+        error::ast_type_err("SimpleStmt", "Expression", lex.peek_span());
+    }
+    lex.eat(&Token::Semicolon);
+
+    let mut expr = None;
+    if !lex.peek().same_type(&Token::Semicolon) {
+        expr = Some(parse_expr(lex, true));
+    }
+    lex.eat(&Token::Semicolon);
+
+    let mut stmt2 = None;
+    if !lex.peek().same_type(&Token::LBrace) {
+        stmt2 = Some(parse_simple_stmt(lex, true));
     }
+
+    return ast::ForClause {
+        stmt1: Some(simple),
+        expr,
+        stmt2,
+    };
 }
 
-fn parse_simple_stmt(lex: &mut Lexer) -> ast::SimpleStmt {
-    match lex.peek() {
-        // Empty Stmt
-        Token::Semicolon => {
+// The operator an assignment like += applies, or None for plain =
+fn assign_op(tok: &Token) -> Option<Option<ast::Operator>> {
+    let op = match tok {
+        Token::Equal => None,
+        Token::PlusEqual => Some(ast::Operator::Plus),
+        Token::MinusEqual => Some(ast::Operator::Minus),
+        Token::TimesEqual => Some(ast::Operator::Times),
+        Token::DivEqual => Some(ast::Operator::Divide),
+        Token::ModEqual => Some(ast::Operator::Mod),
+        Token::AndEqual => Some(ast::Operator::And),
+        Token::OrEqual => Some(ast::Operator::Or),
+        Token::XorEqual => Some(ast::Operator::Xor),
+        Token::LShiftEqual => Some(ast::Operator::LShift),
+        Token::RShiftEqual => Some(ast::Operator::RShift),
+        Token::AndNotEqual => Some(ast::Operator::AndNot),
+        _ => return None,
+    };
+    return Some(op);
+}
+
+fn parse_simple_stmt(lex: &mut Lexer, header: bool) -> ast::SimpleStmt {
+    // Empty Stmt
+    if lex.peek().same_type(&Token::Semicolon) {
+        return ast::SimpleStmt::EmptyStmt;
+    }
+
+    // Every other kind starts with an expression, or a list of them
+    let mut exprs = parse_expr_list(lex, None, header);
+    let tok = lex.peek().clone();
+
+    // Short Var Decl
+    if tok.same_type(&Token::Assignment) {
+        let mut idents = Vec::new();
+        for expr in &exprs.exprs {
+            match expr {
+                ast::Expression::PrimaryExpr(prim) => match prim.as_ref() {
+                    ast::PrimaryExpr::Operand(ast::Operand::Op(name)) => idents.push(*name),
+                    _ => error::ast_type_err("Expression", "identifier", lex.peek_span()),
+                },
+                _ => error::ast_type_err("Expression", "identifier", lex.peek_span()),
+            }
+        }
+        lex.eat(&Token::Assignment);
+        return ast::SimpleStmt::ShortValDecl(idents, parse_expr_list(lex, None, header));
+    }
+
+    // Assignment
+    if let Some(op) = assign_op(&tok) {
+        lex.next_token();
+        return ast::SimpleStmt::Assignment(exprs, op, parse_expr_list(lex, None, header));
+    }
+
+    // The rest only take one expression
+    if exprs.exprs.len() > 1 {
+        error::token_type_err(&tok, &Token::Equal, lex.peek_span());
+    }
+    let expr = exprs.exprs.pop().unwrap();
+    match tok {
+        Token::PlusPlus => {
             lex.next_token();
-            return ast::SimpleStmt::EmptyStmt;
+            return ast::SimpleStmt::IncDecStmt(expr, ast::IncDecType::Inc);
         }
-        // Short Var Decl
-        Token::Ident(name) => {
-            let mut ident_list = Vec::new();
-            ident_list.push(name.to_string());
+        Token::MinusMinus => {
             lex.next_token();
-            while lex.peek().same_type(&Token::Comma) {
-                lex.next_token();
-                if let Token::Ident(name2) = lex.peek() {
-                    ident_list.push(name2.to_string());
-                } else {
-                    error::token_type_err(lex.peek(), &Token::Ident("".to_string()));
-                }
-            }
-            lex.eat(&Token::Assignment);
-            return ast::SimpleStmt::ShortValDecl(ident_list, parse_expr_list(lex, None));
+            return ast::SimpleStmt::IncDecStmt(expr, ast::IncDecType::Dec);
         }
-
-        // Something with an expr
         _ => {
-            let expr = parse_expr(lex);
-            match lex.peek() {
-                Token::Semicolon => {
-                    return ast::SimpleStmt::ExprStmt(expr);
-                }
-                Token::PlusPlus => {
-                    return ast::SimpleStmt::IncDecStmt(expr, ast::IncDecType::Inc);
-                }
-                Token::MinusMinus => {
-                    return ast::SimpleStmt::IncDecStmt(expr, ast::IncDecType::Dec);
-                }
-                Token::Comma => {
-                    let expr_list = parse_expr_list(lex, Some(expr));
-                    let op;
-                    match lex.peek() {
-                        Token::Plus => op = Some(ast::Operator::Plus),
-                        Token::Minus => op = Some(ast::Operator::Minus),
-                        Token::Aster => op = Some(ast::Operator::Times),
-                        Token::FSlash => op = Some(ast::Operator::Divide),
-                        Token::Percent => op = Some(ast::Operator::Mod),
-                        Token::RShift => op = Some(ast::Operator::RShift),
-                        Token::LShift => op = Some(ast::Operator::LShift),
-                        Token::Xor => op = Some(ast::Operator::Xor),
-                        Token::AndNot => op = Some(ast::Operator::AndNot),
-                        Token::Amper => op = Some(ast::Operator::And),
-                        Token::Equal => op = None,
-                        _ => {
-                            error::token_type_err(lex.peek(), &Token::Equal);
-                            todo!();
-                        },
-                    }
-                    lex.eat(&Token::Equal);
-                    return ast::SimpleStmt::Assignment(expr_list, op, parse_expr_list(lex, None));
-                }
-                _ => {
-                    error::token_type_err(lex.peek(), &Token::Semicolon);
-
-                    // TODO Maybe add like an error stmt
-                    panic!();
-                }
-            }
+            return ast::SimpleStmt::ExprStmt(expr);
         }
     }
 }
 
-fn parse_expr_list(lex: &mut Lexer, first: Option<ast::Expression>) -> ast::ExprList {
+fn parse_expr_list(lex: &mut Lexer, first: Option<ast::Expression>, header: bool) -> ast::ExprList {
     let expr;
     if let Some(expr1) = first {
         expr = expr1;
     } else {
-        expr = parse_expr(lex);
+        expr = parse_expr(lex, header);
     }
     let mut list = Vec::new();
     list.push(expr);
     while lex.peek().same_type(&Token::Comma) {
         lex.eat(&Token::Comma);
-        list.push(parse_expr(lex));
+        list.push(parse_expr(lex, header));
     }
     return ast::ExprList{
         exprs: list,
     };
 }
 
-fn parse_expr(lex: &mut Lexer) -> ast::Expression {
-    let left;
-    match lex.peek() {
-        // Unary Exprs
-        Token::Plus => {
-            lex.eat(&Token::Plus);
-            left = ast::Expression::Unary(ast::Operator::Plus, Box::from(parse_unary(lex)));
-        }
-        Token::Minus => {
-            lex.eat(&Token::Minus);
-            left = ast::Expression::Unary(ast::Operator::Minus, Box::from(parse_unary(lex)));
-        }
-        Token::Bang => {
-            lex.eat(&Token::Bang);
-            left = ast::Expression::Unary(ast::Operator::Not, Box::from(parse_unary(lex)));
-        }
-        Token::Xor => {
-            lex.eat(&Token::Xor);
-            left = ast::Expression::Unary(ast::Operator::Xor, Box::from(parse_unary(lex)));
-        }
-        Token::Aster => {
-            lex.eat(&Token::Aster);
-            left = ast::Expression::Unary(ast::Operator::Times, Box::from(parse_unary(lex)));
-        }
-        Token::Amper => {
-            lex.eat(&Token::Amper);
-            left = ast::Expression::Unary(ast::Operator::And, Box::from(parse_unary(lex)));
-        }
-        // PrimaryExpr
-        _ => {
-            left = ast::Expression::PrimaryExpr(Box::from(parse_primary(lex)));
-        }
-    }
-    // binary op if exists
-    let op;
-
-    // check if expr is 
-    match lex.peek() {
-        Token::Aster => op = ast::Operator::Times,
-        Token::FSlash => op = ast::Operator::Divide,
-        Token::Percent => op = ast::Operator::Mod,
-        Token::Amper => op = ast::Operator::And,
-        Token::Or => op = ast::Operator::Or,
-        Token::Xor => op = ast::Operator::Xor,
-        Token::LShift => op = ast::Operator::LShift,
-        Token::RShift => op = ast::Operator::RShift,
-        Token::AndNot => op = ast::Operator::AndNot,
-        Token::LogAnd => op = ast::Operator::LogAnd,
-        Token::LogOr => op = ast::Operator::LogOr,
-        Token::EqualEqual => op = ast::Operator::EqualEqual,
-        Token::Less => op = ast::Operator::Less,
-        Token::Greater => op = ast::Operator::Greater,
-        Token::BangEqual => op = ast::Operator::NotEqual,
-        Token::LessEqual => op = ast::Operator::LessEqual,
-        Token::GreaterEqual => op = ast::Operator::GreaterEqual,
-        _ => {
-            return left;
-        }
-    }
-    lex.next_token();
+// header is set in the header of an if, for, or switch. There a { after a bare
+// type name opens the body, so it can't start a composite literal. Anything
+// inside parentheses, brackets, or braces is out of the header again
+fn parse_expr(lex: &mut Lexer, header: bool) -> ast::Expression {
+    return parse_binary(lex, 1, header);
+}
 
-    // get right expr
-    let right;
+// The binary operator a token spells, along with its precedence. Higher binds
+// tighter, following Go's five levels
+fn binary_op(tok: &Token) -> Option<(ast::Operator, u8)> {
+    let op = match tok {
+        Token::Aster => (ast::Operator::Times, 5),
+        Token::FSlash => (ast::Operator::Divide, 5),
+        Token::Percent => (ast::Operator::Mod, 5),
+        Token::LShift => (ast::Operator::LShift, 5),
+        Token::RShift => (ast::Operator::RShift, 5),
+        Token::Amper => (ast::Operator::And, 5),
+        Token::AndNot => (ast::Operator::AndNot, 5),
+        Token::Plus => (ast::Operator::Plus, 4),
+        Token::Minus => (ast::Operator::Minus, 4),
+        Token::Or => (ast::Operator::Or, 4),
+        Token::Xor => (ast::Operator::Xor, 4),
+        Token::EqualEqual => (ast::Operator::EqualEqual, 3),
+        Token::BangEqual => (ast::Operator::NotEqual, 3),
+        Token::Less => (ast::Operator::Less, 3),
+        Token::LessEqual => (ast::Operator::LessEqual, 3),
+        Token::Greater => (ast::Operator::Greater, 3),
+        Token::GreaterEqual => (ast::Operator::GreaterEqual, 3),
+        Token::LogAnd => (ast::Operator::LogAnd, 2),
+        Token::LogOr => (ast::Operator::LogOr, 1),
+        _ => return None,
+    };
+    return Some(op);
+}
 
-    match lex.peek() {
-        // Unary Exprs
-        Token::Plus => {
-            lex.eat(&Token::Plus);
-            right = ast::Expression::Unary(ast::Operator::Plus, Box::from(parse_unary(lex)));
-        }
-        Token::Minus => {
-            lex.eat(&Token::Minus);
-            right = ast::Expression::Unary(ast::Operator::Minus, Box::from(parse_unary(lex)));
-        }
-        Token::Bang => {
-            lex.eat(&Token::Bang);
-            right = ast::Expression::Unary(ast::Operator::Not, Box::from(parse_unary(lex)));
-        }
-        Token::Xor => {
-            lex.eat(&Token::Xor);
-            right = ast::Expression::Unary(ast::Operator::Xor, Box::from(parse_unary(lex)));
-        }
-        Token::Aster => {
-            lex.eat(&Token::Aster);
-            right = ast::Expression::Unary(ast::Operator::Times, Box::from(parse_unary(lex)));
-        }
-        Token::Amper => {
-            lex.eat(&Token::Amper);
-            right = ast::Expression::Unary(ast::Operator::And, Box::from(parse_unary(lex)));
-        }
-        // PrimaryExpr
-        _ => {
-            right = ast::Expression::PrimaryExpr(Box::from(parse_primary(lex)));
+// Parses unary expressions joined by binary operators of at least min_prec.
+// The right side of each operator only takes tighter operators, which makes
+// operators of the same level associate to the left
+fn parse_binary(lex: &mut Lexer, min_prec: u8, header: bool) -> ast::Expression {
+    let mut left = parse_unary(lex, header);
+    while let Some((op, prec)) = binary_op(lex.peek()) {
+        if prec < min_prec {
+            break;
         }
+        lex.next_token();
+        let right = parse_binary(lex, prec + 1, header);
+        left = ast::Expression::Binary(Box::from(left), op, Box::from(right));
     }
-    
-    return ast::Expression::Binary(Box::from(left), op, Box::from(right));
+    return left;
 }
 
-fn parse_unary(lex: &mut Lexer) -> ast::Expression {
+fn parse_unary(lex: &mut Lexer, header: bool) -> ast::Expression {
     match lex.peek() {
         // Unary Exprs
         Token::Plus => {
             lex.eat(&Token::Plus);
-            return ast::Expression::Unary(ast::Operator::Plus, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::Plus, Box::from(parse_unary(lex, header)));
         }
         Token::Minus => {
             lex.eat(&Token::Minus);
-            return ast::Expression::Unary(ast::Operator::Minus, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::Minus, Box::from(parse_unary(lex, header)));
         }
         Token::Bang => {
             lex.eat(&Token::Bang);
-            return ast::Expression::Unary(ast::Operator::Not, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::Not, Box::from(parse_unary(lex, header)));
         }
         Token::Xor => {
             lex.eat(&Token::Xor);
-            return ast::Expression::Unary(ast::Operator::Xor, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::Xor, Box::from(parse_unary(lex, header)));
         }
         Token::Aster => {
             lex.eat(&Token::Aster);
-            return ast::Expression::Unary(ast::Operator::Times, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::Deref, Box::from(parse_unary(lex, header)));
         }
         Token::Amper => {
             lex.eat(&Token::Amper);
-            return ast::Expression::Unary(ast::Operator::And, Box::from(parse_unary(lex)));
+            return ast::Expression::Unary(ast::Operator::AddrOf, Box::from(parse_unary(lex, header)));
         }
         // PrimaryExpr
         _ => {
-            return ast::Expression::PrimaryExpr(Box::from(parse_primary(lex)));
+            return ast::Expression::PrimaryExpr(Box::from(parse_primary(lex, header)));
         }
     }
 }
 
-fn parse_primary(lex: &mut Lexer) -> ast::PrimaryExpr {
+fn parse_primary(lex: &mut Lexer, header: bool) -> ast::PrimaryExpr {
     let prim_expr;
     match lex.peek() {
-        // The functions added
-        Token::Append => {
-            lex.eat(&Token::Append);
-            lex.eat(&Token::LParen);
-            let expr1 = parse_expr(lex);
-            lex.eat(&Token::Comma);
-            let expr2 = parse_expr(lex);
-            lex.eat(&Token::RParen);
-            return ast::PrimaryExpr::Append(expr1, expr2);
-        }
-        Token::Cap => {
-            lex.eat(&Token::Cap);
-            lex.eat(&Token::LParen);
-            let expr = parse_expr(lex);
-            lex.eat(&Token::RParen);
-            return ast::PrimaryExpr::Capacity(expr);
-        }
-        Token::Len => {
-            lex.eat(&Token::Len);
-            lex.eat(&Token::LParen);
-            let expr = parse_expr(lex);
-            lex.eat(&Token::RParen);
-            return ast::PrimaryExpr::Length(expr);
+        // Composite literal or Type Conversion
+        Token::LBrack | Token::Struct => {
+            let lit_type = parse_type(lex);
+            if lex.peek().same_type(&Token::LBrace) {
+                let lit = ast::Literal::Composite(parse_composite_lit(lex, Some(lit_type)));
+                prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
+            } else {
+                prim_expr = parse_conversion(lex, lit_type);
+            }
         }
         
-        // Type Conversion
-        Token::LBrack => {
-            let conv_type = parse_type(lex);
-            lex.eat(&Token::LParen);
-            let expr = parse_expr(lex);
-            lex.eat(&Token::RParen);
-            return ast::PrimaryExpr::Call(None, Some(conv_type), Some(ast::ExprList{exprs: vec![expr]}));
-        } 
-        
         // Either Conversion or Operand
         Token::LParen => {
-            lex.eat(&Token::LParen);
-            
-            // either an expression or a type
-            // TODO IDK what to do here
-            // Get rid of all parenths
-            // If left bracket then def a type
-            // If ident, could be either
-            // if Lparen, could be either????? FFFFFFF
-            // Otherwise def an expr
-            // I GIVE UP
-            // We just doing an expr and ident will just be the thing below
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Expr(parse_expr(lex)));
-
-            lex.eat(&Token::RParen);
+            // A bracket right after the paren can only start a type, either of a
+            // conversion as in ([]int)(x) or of a literal as in ([]int{1}[0])
+            if lex.peek_nth(1).same_type(&Token::LBrack) {
+                lex.eat(&Token::LParen);
+                let paren_type = parse_type(lex);
+                if lex.peek().same_type(&Token::LBrace) {
+                    let lit = ast::Literal::Composite(parse_composite_lit(lex, Some(paren_type)));
+                    let inner = parse_postfix(lex, ast::PrimaryExpr::Operand(ast::Operand::Literal(lit)));
+                    lex.eat(&Token::RParen);
+                    let expr = ast::Expression::PrimaryExpr(Box::from(inner));
+                    prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Expr(expr));
+                } else {
+                    lex.eat(&Token::RParen);
+                    prim_expr = parse_conversion(lex, paren_type);
+                }
+            } else {
+                // A parenthesized name could still be a type, as in (T)(x). That is
+                // left for resolution to tell apart from a call
+                lex.eat(&Token::LParen);
+                let expr = parse_expr(lex, false);
+                prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Expr(expr));
+                lex.eat(&Token::RParen);
+            }
         }
 
-        // Operand OpName
+        // Operand OpName, or the type of a composite literal
         Token::Ident(name) => {
-            let ident_name = name.to_string();
+            let ident_name = *name;
+            lex.next_token();
+            if lex.peek().same_type(&Token::LBrace) && !header {
+                let lit_type = ast::Type::Named(ident_name);
+                let lit = ast::Literal::Composite(parse_composite_lit(lex, Some(lit_type)));
+                prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
+            } else {
+                prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Op(ident_name));
+            }
+        }
+        Token::BlankIdent => {
             lex.next_token();
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Op(ident_name));
+            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Op(Symbol::intern("_")));
         }
 
         // Operand Literal
         Token::Int(num) => {
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(ast::Literal::Integer(num.clone())));
+            let lit = ast::Literal::Integer(*num);
+            lex.next_token();
+            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
         }
         Token::Float(num) => {
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(ast::Literal::Float(num.clone())));
+            let lit = ast::Literal::Float(*num);
+            lex.next_token();
+            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
         }
         Token::String(word) => {
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(ast::Literal::String(word.clone())));
+            let lit = ast::Literal::String(word.clone());
+            lex.next_token();
+            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
         }
         Token::Rune(character) => {
-            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(ast::Literal::Rune(character.clone())));
+            let lit = ast::Literal::Rune(*character);
+            lex.next_token();
+            prim_expr = ast::PrimaryExpr::Operand(ast::Operand::Literal(lit));
         }
         _ => {
-            error::token_type_err(lex.peek(),&Token::Ident("".to_string()) );
-            todo!();
+            error::expected_err(lex.peek(), "identifier", lex.peek_span());
         }
     }
 
-    // Check if there's a selector/index/call
-    match lex.peek() {
-        // Selector
-        Token::Period => {
-            lex.eat(&Token::Period);
-            if let Token::Ident(name) = lex.peek() {
-                return ast::PrimaryExpr::Selector(Box::from(prim_expr), name.to_string());
-            } else {
-                error::token_type_err(lex.peek(), &Token::Ident("".to_string()));
-                todo!();
+    return parse_postfix(lex, prim_expr);
+}
+
+// Applies any selectors, indexes, slices, and calls, each to the result of the last
+fn parse_postfix(lex: &mut Lexer, mut prim_expr: ast::PrimaryExpr) -> ast::PrimaryExpr {
+    loop {
+        match lex.peek() {
+            // Selector
+            Token::Period => {
+                lex.eat(&Token::Period);
+                let name = parse_ident(lex);
+                prim_expr = ast::PrimaryExpr::Selector(Box::from(prim_expr), name);
             }
-        }
 
-        // Call
-        Token::LParen => {
-            lex.eat(&Token::LParen);
-            if lex.peek().same_type(&Token::RParen) {
+            // Call
+            Token::LParen => {
+                lex.eat(&Token::LParen);
+                let type_arg = parse_type_arg(lex);
+                let mut args = None;
+                if !lex.peek().same_type(&Token::RParen) {
+                    args = Some(parse_expr_list(lex, None, false));
+                }
                 lex.eat(&Token::RParen);
-                return ast::PrimaryExpr::Call(Some(Box::from(prim_expr)), None, None);
-            } 
-            let expr_list = parse_expr_list(lex, None);
-            lex.eat(&Token::RParen);
-            return ast::PrimaryExpr::Call(Some(Box::from(prim_expr)), None, Some(expr_list));
-        }
+                prim_expr = ast::PrimaryExpr::Call(Some(Box::from(prim_expr)), type_arg, args);
+            }
 
-        // Index
-        Token::LBrack => {
-            lex.eat(&Token::LBrack);
-            let expr = parse_expr(lex);
-            lex.eat(&Token::RBrack);
-            return ast::PrimaryExpr::Index(Box::from(prim_expr), expr);
-        }
+            // Index or Slice
+            Token::LBrack => {
+                lex.eat(&Token::LBrack);
+                let mut low = None;
+                if !lex.peek().same_type(&Token::Colon) {
+                    low = Some(parse_expr(lex, false));
+                }
 
-        _ => {
-            return prim_expr;
+                if lex.peek().same_type(&Token::Colon) {
+                    lex.eat(&Token::Colon);
+                    let mut high = None;
+                    if !lex.peek().same_type(&Token::RBrack) {
+                        high = Some(parse_expr(lex, false));
+                    }
+                    lex.eat(&Token::RBrack);
+                    prim_expr = ast::PrimaryExpr::Slice(Box::from(prim_expr), low, high);
+                } else {
+                    lex.eat(&Token::RBrack);
+                    prim_expr = ast::PrimaryExpr::Index(Box::from(prim_expr), low.unwrap());
+                }
+            }
+
+            _ => {
+                return prim_expr;
+            }
         }
     }
 }
 
-fn parse_type_decl(lex: &mut Lexer, type_env: &mut )  {
+// A call like make([]bool, 9) takes a type as its first argument. Only a
+// type spelled with [ or struct is caught here; a bare name is parsed as an
+// expression and left for resolution
+fn parse_type_arg(lex: &mut Lexer) -> Option<ast::Type> {
+    if !(lex.peek().same_type(&Token::LBrack) || lex.peek().same_type(&Token::Struct)) {
+        return None;
+    }
+
+    // Back up if it turns out to be a conversion or composite literal
+    let start = lex.checkpoint();
+    let arg_type = parse_type(lex);
+    if lex.peek().same_type(&Token::Comma) {
+        lex.eat(&Token::Comma);
+        return Some(arg_type);
+    } else if lex.peek().same_type(&Token::RParen) {
+        return Some(arg_type);
+    }
+    lex.rewind(start);
+    return None;
+}
 
+// T(x), once the type has been parsed
+fn parse_conversion(lex: &mut Lexer, conv_type: ast::Type) -> ast::PrimaryExpr {
+    lex.eat(&Token::LParen);
+    let expr = parse_expr(lex, false);
+    lex.eat(&Token::RParen);
+    return ast::PrimaryExpr::Call(None, Some(conv_type), Some(ast::ExprList{exprs: vec![expr]}));
 }
 
-fn parse_var_decl(lex: &mut Lexer) {
+// The braced part of a composite literal, positional or keyed
+fn parse_composite_lit(lex: &mut Lexer, lit_type: Option<ast::Type>) -> ast::CompositeLit {
+    lex.eat(&Token::LBrace);
+    let mut elems = Vec::new();
+    while !lex.peek().same_type(&Token::RBrace) {
+        let first = parse_element(lex);
+        if lex.peek().same_type(&Token::Colon) {
+            lex.eat(&Token::Colon);
+            elems.push(ast::KeyedElement { key: Some(first), value: parse_element(lex) });
+        } else {
+            elems.push(ast::KeyedElement { key: None, value: first });
+        }
+
+        // The last one doesn't need a comma
+        if !lex.peek().same_type(&Token::RBrace) {
+            lex.eat(&Token::Comma);
+        }
+    }
+    lex.eat(&Token::RBrace);
+    return ast::CompositeLit { lit_type, elems };
+}
 
-}
\ No newline at end of file
+// A bare { inside a composite literal starts another one, with its type elided
+fn parse_element(lex: &mut Lexer) -> ast::Element {
+    if lex.peek().same_type(&Token::LBrace) {
+        return ast::Element::Lit(parse_composite_lit(lex, None));
+    }
+    return ast::Element::Expr(parse_expr(lex, false));
+}
@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{Declaration, FuncLiteral, Method, Operand, PrimaryExpr, TopLevelDecl, Type, VarValue, AST};
+use crate::symbol::Symbol;
+
+// What a name refers to
+#[derive(Clone, Debug)]
+pub enum Entity {
+    Type(Type),
+    // A declared type, holding its underlying type
+    TypeDef(Type),
+    Const(Type, VarValue),
+    // A variable, with its type if it was written out. Otherwise the type
+    // comes from the initializer
+    Var(Option<Type>),
+    Func,
+    Builtin(Builtin),
+}
+
+// The predeclared functions GoLite keeps. They're called like any other
+// function, so a program can declare its own function of the same name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    Append,
+    Len,
+    Cap,
+}
+
+// What x.name picks out of x
+#[derive(Debug)]
+pub enum Selection<'a> {
+    Field(Type),
+    Method(&'a Method),
+}
+
+// Nested blocks of declarations. The outermost block is the universe, which
+// holds the predeclared names; everything a program declares lives in a
+// block inside it, so a program may shadow any of them
+pub struct Scope {
+    blocks: Vec<HashMap<Symbol, Entity>>,
+}
+
+// The names Go predeclares that GoLite keeps
+fn universe() -> HashMap<Symbol, Entity> {
+    let mut block = HashMap::new();
+    block.insert(Symbol::intern("int"), Entity::Type(Type::Integer));
+    block.insert(Symbol::intern("float64"), Entity::Type(Type::Float));
+    block.insert(Symbol::intern("rune"), Entity::Type(Type::Rune));
+    block.insert(Symbol::intern("bool"), Entity::Type(Type::Bool));
+    block.insert(Symbol::intern("string"), Entity::Type(Type::String));
+    block.insert(Symbol::intern("true"), Entity::Const(Type::Bool, VarValue::Bool(true)));
+    block.insert(Symbol::intern("false"), Entity::Const(Type::Bool, VarValue::Bool(false)));
+    block.insert(Symbol::intern("nil"), Entity::Const(Type::Nil, VarValue::Nil));
+    block.insert(Symbol::intern("append"), Entity::Builtin(Builtin::Append));
+    block.insert(Symbol::intern("len"), Entity::Builtin(Builtin::Len));
+    block.insert(Symbol::intern("cap"), Entity::Builtin(Builtin::Cap));
+    return block;
+}
+
+impl Scope {
+    // The universe with an empty package block inside it
+    pub fn new() -> Self {
+        return Scope { blocks: vec![universe(), HashMap::new()] };
+    }
+
+    pub fn push(&mut self) {
+        self.blocks.push(HashMap::new());
+    }
+
+    pub fn pop(&mut self) {
+        // The universe and package blocks always stay
+        if self.blocks.len() > 2 {
+            self.blocks.pop();
+        }
+    }
+
+    // Declares a name in the innermost block. Shadowing an outer name is
+    // fine, but declaring one twice in the same block is an error
+    pub fn define(&mut self, name: Symbol, entity: Entity) -> Result<(), String> {
+        let block = self.blocks.last_mut().unwrap();
+        if block.contains_key(&name) {
+            return Err(format!("{} redeclared in this block", name));
+        }
+        block.insert(name, entity);
+        return Ok(());
+    }
+
+    // Finds the innermost declaration of a name
+    pub fn lookup(&self, name: Symbol) -> Option<&Entity> {
+        return self.blocks.iter().rev().find_map(|block| block.get(&name));
+    }
+
+    // The builtin a call's function refers to, if it's a bare name that hasn't
+    // been declared again somewhere in scope
+    pub fn builtin(&self, func: &PrimaryExpr) -> Option<Builtin> {
+        if let PrimaryExpr::Operand(Operand::Op(name)) = func {
+            if let Some(Entity::Builtin(builtin)) = self.lookup(*name) {
+                return Some(*builtin);
+            }
+        }
+        return None;
+    }
+
+    // Declares every name at package level and checks every type written
+    // there. The package's types are declared first, so anything else can use
+    // them. Then come the variables and functions, which can shadow the
+    // predeclared names, along with the types in their declarations, and last
+    // the method receivers
+    pub fn resolve_package(&mut self, ast: &AST) -> Result<(), String> {
+        self.declare_package_types(ast)?;
+        for decl in &ast.decls {
+            match decl {
+                TopLevelDecl::Decl(Declaration::Var(specs)) => {
+                    for spec in specs {
+                        let var_type = match &spec.var_type {
+                            Some(ty) => Some(self.resolve_type(ty)?),
+                            None => None,
+                        };
+                        for name in &spec.ident_list {
+                            if *name != "_" {
+                                self.define(*name, Entity::Var(var_type.clone()))?;
+                            }
+                        }
+                    }
+                }
+                TopLevelDecl::Func(name, func) => {
+                    // A package can have any number of init functions, and
+                    // nothing can refer to them
+                    if *name != "init" && *name != "_" {
+                        self.define(*name, Entity::Func)?;
+                    }
+                    self.resolve_signature(func)?;
+                }
+                TopLevelDecl::Method(method) => self.resolve_signature(&method.func)?,
+                _ => {}
+            }
+        }
+        return self.declare_methods(ast);
+    }
+
+    fn resolve_signature(&self, func: &FuncLiteral) -> Result<(), String> {
+        for list in [&func.params, &func.ret].into_iter().flatten() {
+            for (_, ty) in &list.params {
+                self.resolve_type(ty)?;
+            }
+        }
+        return Ok(());
+    }
+
+    // Declares every type at package level before resolving any of them, so a
+    // type can be used ahead of the declaration that defines it
+    pub fn declare_package_types(&mut self, ast: &AST) -> Result<(), String> {
+        let mut specs = Vec::new();
+        for decl in &ast.decls {
+            if let TopLevelDecl::Decl(Declaration::Type(decl_specs)) = decl {
+                specs.extend(decl_specs.iter().cloned());
+            }
+        }
+        return self.declare_types(&specs);
+    }
+
+    // Declares a group of types in the innermost block. They can refer to each
+    // other in any order
+    pub fn declare_types(&mut self, specs: &[(Symbol, Type)]) -> Result<(), String> {
+        for (name, ty) in specs {
+            self.define(*name, Entity::TypeDef(ty.clone()))?;
+        }
+        for (name, ty) in specs {
+            let resolved = self.resolve_type(ty)?;
+            self.blocks.last_mut().unwrap().insert(*name, Entity::TypeDef(resolved));
+        }
+        for (name, _) in specs {
+            self.check_recursion(*name, *name, &mut Vec::new())?;
+        }
+        return Ok(());
+    }
+
+    // A type can only contain itself behind a slice or pointer, since anything
+    // else would make it infinitely large. Walks the types stored inline in
+    // the declared type current, looking for name
+    fn check_recursion(&self, name: Symbol, current: Symbol, seen: &mut Vec<Symbol>) -> Result<(), String> {
+        if seen.contains(&current) {
+            return Ok(());
+        }
+        seen.push(current);
+        let mut inline = Vec::new();
+        if let Some(Entity::TypeDef(underlying)) = self.lookup(current) {
+            inline_names(underlying, &mut inline);
+        }
+        for next in inline {
+            if next == name {
+                return Err(format!("invalid recursive type {}", name));
+            }
+            self.check_recursion(name, next, seen)?;
+        }
+        return Ok(());
+    }
+
+    // Follows declared type names down to the type they're made from
+    pub fn underlying(&self, ty: &Type) -> Type {
+        if let Type::Named(name) = ty {
+            if let Some(Entity::TypeDef(underlying)) = self.lookup(*name) {
+                return self.underlying(underlying);
+            }
+        }
+        return ty.clone();
+    }
+
+    // The type of base.field. Selecting through a pointer to a struct reads the
+    // struct's field, as if written (*base).field
+    pub fn field_type(&self, base: &Type, field: Symbol) -> Result<Type, String> {
+        let mut ty = self.underlying(base);
+        if let Type::Pointer(pointee) = &ty {
+            ty = self.underlying(pointee);
+        }
+        if let Type::Struct(fields) = &ty {
+            if let Some((_, field_type)) = fields.iter().find(|(name, _)| *name == field) {
+                return Ok(field_type.clone());
+            }
+        }
+        return Err(format!("{} has no field {}", base, field));
+    }
+
+    // Checks each type's methods against the package's types. The receiver has
+    // to be a type declared in the package that isn't itself a pointer, a type
+    // can't have two methods of the same name, and no method can share its
+    // name with one of the type's fields
+    pub fn declare_methods(&self, ast: &AST) -> Result<(), String> {
+        let mut declared = HashSet::new();
+        for method in ast.methods() {
+            let base = method.recv.base;
+            match self.lookup(base) {
+                Some(Entity::TypeDef(_)) => {}
+                Some(_) => return Err(format!("invalid receiver type {}", base)),
+                None => return Err(format!("undefined: {}", base)),
+            }
+            if let Type::Pointer(_) = self.underlying(&Type::Named(base)) {
+                return Err(format!("invalid receiver type {} (pointer or interface type)", base));
+            }
+            if !declared.insert((base, method.name)) {
+                return Err(format!("duplicate method {}.{}", base, method.name));
+            }
+            if self.field_type(&Type::Named(base), method.name).is_ok() {
+                return Err(format!("field and method with the same name {}", method.name));
+            }
+        }
+        return Ok(());
+    }
+
+    // Resolves base.name to a field or a method. Fields are found first. A
+    // method can be called on a value or a pointer whatever its receiver is,
+    // since Go takes the address or dereferences as needed
+    pub fn select<'a>(&self, ast: &'a AST, base: &Type, name: Symbol) -> Result<Selection<'a>, String> {
+        if let Ok(ty) = self.field_type(base, name) {
+            return Ok(Selection::Field(ty));
+        }
+        let named = match base {
+            Type::Pointer(pointee) => pointee,
+            _ => base,
+        };
+        if let Type::Named(type_name) = named {
+            if let Some(method) = ast.methods_of(*type_name).find(|method| method.name == name) {
+                return Ok(Selection::Method(method));
+            }
+        }
+        return Err(format!("{} has no field or method {}", base, name));
+    }
+
+    // Replaces every predeclared type name with the type it's bound to, and
+    // checks that every other name refers to a declared type
+    pub fn resolve_type(&self, ty: &Type) -> Result<Type, String> {
+        match ty {
+            Type::Named(name) => match self.lookup(*name) {
+                Some(Entity::Type(ty)) => return Ok(ty.clone()),
+                Some(Entity::TypeDef(_)) => return Ok(ty.clone()),
+                Some(_) => return Err(format!("{} is not a type", name)),
+                None => return Err(format!("undefined: {}", name)),
+            },
+            Type::Array(len, elem) => {
+                return Ok(Type::Array(*len, Box::new(self.resolve_type(elem)?)));
+            }
+            Type::Slice(elem) => {
+                return Ok(Type::Slice(Box::new(self.resolve_type(elem)?)));
+            }
+            Type::Pointer(elem) => {
+                return Ok(Type::Pointer(Box::new(self.resolve_type(elem)?)));
+            }
+            Type::Struct(fields) => {
+                let mut resolved = Vec::new();
+                for (name, field_type) in fields {
+                    resolved.push((*name, self.resolve_type(field_type)?));
+                }
+                return Ok(Type::Struct(resolved));
+            }
+            _ => return Ok(ty.clone()),
+        }
+    }
+}
+
+// Collects the declared types that a type holds directly, rather than behind
+// a slice or pointer
+fn inline_names(ty: &Type, names: &mut Vec<Symbol>) {
+    match ty {
+        Type::Named(name) => names.push(*name),
+        Type::Array(_, elem) => inline_names(elem, names),
+        Type::Struct(fields) => {
+            for (_, field_type) in fields {
+                inline_names(field_type, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
+
+// An interned name. Two symbols are equal exactly when their text is, so
+// comparing and hashing them never touches the string itself
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+// Maps each distinct name to its symbol and back. The strings are leaked so
+// that lookups can hand out &'static str; names live as long as the compiler
+#[derive(Default)]
+struct Interner {
+    names: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+// One interner for the whole process, so a symbol means the same name on
+// every thread
+static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+fn interner() -> MutexGuard<'static, Interner> {
+    // Nothing panics partway through an update, so even a poisoned lock
+    // guards a consistent interner
+    return INTERNER.get_or_init(Mutex::default).lock().unwrap_or_else(PoisonError::into_inner);
+}
+
+impl Interner {
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&sym) = self.names.get(name) {
+            return sym;
+        }
+        let name: &'static str = Box::leak(name.into());
+        let sym = Symbol(self.strings.len() as u32);
+        self.strings.push(name);
+        self.names.insert(name, sym);
+        return sym;
+    }
+}
+
+impl Symbol {
+    // Returns the symbol for a name, adding it to the interner if it's new
+    pub fn intern(name: &str) -> Symbol {
+        return interner().intern(name);
+    }
+
+    pub fn as_str(self) -> &'static str {
+        return interner().strings[self.0 as usize];
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        return Symbol::intern(name);
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        return self.as_str() == *other;
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.as_str());
+    }
+}
+
+// Shows the name rather than the index, so token dumps stay readable
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:?}", self.as_str());
+    }
+}
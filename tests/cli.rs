@@ -0,0 +1,46 @@
+#![allow(clippy::needless_return)]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Runs `tokens -` with the given source on stdin
+fn run(src: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_GoLite-Compiler"))
+        .arg("tokens")
+        .arg("-")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Couldn't run the compiler");
+    child.stdin.take().unwrap().write_all(src.as_bytes()).unwrap();
+    return child.wait_with_output().unwrap();
+}
+
+#[test]
+fn tokens_text() {
+    let out = run("x := `a\nb`\n", &[]);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "1:1 Ident \"x\"\n1:3 Assignment \":=\"\n1:6 String \"`a\\nb`\"\n2:3 Semicolon \"\"\n3:1 EOF \"\"\n"
+    );
+}
+
+#[test]
+fn tokens_json() {
+    let out = run("a $\n", &["--json"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        concat!(
+            "{\"tokens\":[",
+            "{\"kind\":\"Ident\",\"lexeme\":\"a\",\"line\":1,\"col\":1,\"start\":0,\"end\":1},",
+            "{\"kind\":\"Semicolon\",\"lexeme\":\"\",\"line\":1,\"col\":4,\"start\":3,\"end\":3},",
+            "{\"kind\":\"EOF\",\"lexeme\":\"\",\"line\":2,\"col\":1,\"start\":4,\"end\":4}],",
+            "\"errors\":[{\"message\":\"invalid character U+0024 '$'\",\"line\":1,\"col\":3}]}\n",
+        )
+    );
+    assert_eq!(String::from_utf8(out.stderr).unwrap(), "<stdin>:1:3: invalid character U+0024 '$'\n");
+}
@@ -0,0 +1,6 @@
+x
+
+
+	
+y
+   
@@ -0,0 +1,11 @@
+x /* inline */ y
+x /* spans
+lines */ y
+x /* trailing */
+y
+f( /* a
+b */ )
+x /* a/b */ y
+x /* ** / * */ y
+x /*/ still open */ y
+x /* last line
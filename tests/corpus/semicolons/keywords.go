@@ -0,0 +1,10 @@
+for {
+	break
+	continue
+}
+switch {
+case x:
+	fallthrough
+default:
+	return
+}
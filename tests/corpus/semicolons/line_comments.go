@@ -0,0 +1,2 @@
+x // comment
+y // comment
\ No newline at end of file
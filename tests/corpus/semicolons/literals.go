@@ -0,0 +1,9 @@
+1
+1.5
+.5
+0x1p-2
+'a'
+"s"
+`raw`
+`multi
+line`
@@ -0,0 +1,9 @@
+func f() {
+}
+if x {
+} else {
+}
+var (
+)
+type t struct {
+}
@@ -0,0 +1,11 @@
+i++
+i--
+f()
+a[i]
+{}
+x +
+y
+x &&
+y
+x =
+1
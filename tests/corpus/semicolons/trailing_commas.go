@@ -0,0 +1,7 @@
+f(a,
+	b,
+)
+x := []int{
+	1,
+	2,
+}
@@ -0,0 +1 @@
+x /* unterminated
\ No newline at end of file
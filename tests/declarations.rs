@@ -0,0 +1,520 @@
+#![allow(clippy::needless_return)]
+
+use golite::ast::{
+    Declaration, Expression, Operand, ParamList, PrimaryExpr, SimpleStmt, Statement, TopLevelDecl, Type, VarValue, AST,
+};
+use golite::lexer::Lexer;
+use golite::parser;
+use golite::resolve::{Builtin, Entity, Scope, Selection};
+use golite::symbol::Symbol;
+
+fn parse(src: &str) -> AST {
+    return parser::parse(&mut Lexer::from_source(src));
+}
+
+// Every type declared in the file, in order
+fn type_decls(ast: &AST) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for decl in &ast.decls {
+        if let TopLevelDecl::Decl(Declaration::Type(specs)) = decl {
+            for (name, ty) in specs {
+                out.push((name.to_string(), format!("{:?}", ty)));
+            }
+        }
+    }
+    return out;
+}
+
+#[test]
+fn test_decl_file() {
+    let mut lex = Lexer::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/test_decl.go")).unwrap();
+    let ast = parser::parse(&mut lex);
+    let names: Vec<String> = type_decls(&ast).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["num", "str", "char", "maybe", "decimal", "some", "another", "point", "square"]);
+}
+
+#[test]
+fn struct_fields_in_order() {
+    let ast = parse("package main\ntype node struct {\n\tx, y int; next []point\n\t_, _ bool\n}\n");
+    let (_, ty) = &type_decls(&ast)[0];
+    assert_eq!(
+        ty,
+        "Struct([(\"x\", Named(\"int\")), (\"y\", Named(\"int\")), (\"next\", Slice(Named(\"point\"))), \
+         (\"_\", Named(\"bool\")), (\"_\", Named(\"bool\"))])"
+    );
+}
+
+#[test]
+fn nested_and_empty_structs() {
+    let ast = parse("package main\nvar p struct { inner struct{}; s struct { a int } }\n");
+    let TopLevelDecl::Decl(Declaration::Var(specs)) = &ast.decls[0] else {
+        panic!("expected a var declaration");
+    };
+    let Some(Type::Struct(fields)) = &specs[0].var_type else {
+        panic!("expected a struct type");
+    };
+    assert_eq!(fields.len(), 2);
+    assert!(matches!(&fields[0].1, Type::Struct(inner) if inner.is_empty()));
+}
+
+#[test]
+#[should_panic(expected = "<input>:4:2: duplicate field x")]
+fn duplicate_field() {
+    parse("package main\ntype point struct {\n\tx, y int\n\tx float64\n}\n");
+}
+
+#[test]
+#[should_panic(expected = "duplicate field a")]
+fn duplicate_field_in_one_list() {
+    parse("package main\ntype t struct { a, b, a int }\n");
+}
+
+#[test]
+#[should_panic(expected = "<input>:2:9: string literal not terminated")]
+fn lexical_errors_stop_parsing() {
+    parse("package main\nvar x = \"abc");
+}
+
+#[test]
+#[should_panic(expected = "there is nothing to communicate with\n<input>:3:9: more than one character in rune literal")]
+fn lexical_and_feature_errors_in_source_order() {
+    parse("package main\nvar c chan int\nvar x = 'ab'\n");
+}
+
+// Declares the package's types and resolves the type of each package-level var
+fn resolve_vars(src: &str) -> Result<Vec<String>, String> {
+    let ast = parse(src);
+    let mut scope = Scope::new();
+    scope.declare_package_types(&ast)?;
+    let mut out = Vec::new();
+    for decl in &ast.decls {
+        if let TopLevelDecl::Decl(Declaration::Var(specs)) = decl {
+            for spec in specs {
+                let ty = scope.resolve_type(spec.var_type.as_ref().unwrap())?;
+                out.push(format!("{:?} = {:?}", ty, scope.underlying(&ty)));
+            }
+        }
+    }
+    return Ok(out);
+}
+
+#[test]
+fn named_types_declared_later() {
+    let vars = resolve_vars(
+        "package main\nvar p point\nvar s []num\ntype point struct { x, y num; next []point }\ntype num int\n",
+    );
+    assert_eq!(
+        vars.unwrap(),
+        [
+            "Named(\"point\") = Struct([(\"x\", Named(\"num\")), (\"y\", Named(\"num\")), (\"next\", Slice(Named(\"point\")))])",
+            "Slice(Named(\"num\")) = Slice(Named(\"num\"))",
+        ]
+    );
+}
+
+#[test]
+fn shadowed_predeclared_type() {
+    let vars = resolve_vars("package main\ntype int float64\nvar x int\nvar y []int\n");
+    assert_eq!(vars.unwrap(), ["Named(\"int\") = Float", "Slice(Named(\"int\")) = Slice(Named(\"int\"))"]);
+}
+
+#[test]
+fn shadowed_true() {
+    let ast = parse("package main\nvar true = 0\nfunc main() {\n\tx := true\n}\n");
+    let mut scope = Scope::new();
+    scope.resolve_package(&ast).unwrap();
+
+    // true is an ordinary name, so the one in main is the program's var
+    let TopLevelDecl::Func(_, main) = &ast.decls[1] else { panic!("expected main") };
+    let Statement::SimpleStmt(SimpleStmt::ShortValDecl(_, values)) = &main.body.stmts[0] else {
+        panic!("expected x := true");
+    };
+    let Expression::PrimaryExpr(prim) = &values.exprs[0] else { panic!("expected a name") };
+    let PrimaryExpr::Operand(Operand::Op(name)) = prim.as_ref() else { panic!("expected a name") };
+    assert!(matches!(scope.lookup(*name), Some(Entity::Var(None))));
+
+    // false is still the predeclared constant, and so is true outside this package
+    assert!(matches!(scope.lookup(Symbol::intern("false")), Some(Entity::Const(Type::Bool, VarValue::Bool(false)))));
+    assert!(matches!(Scope::new().lookup(*name), Some(Entity::Const(Type::Bool, VarValue::Bool(true)))));
+}
+
+#[test]
+fn undefined_and_recursive_types() {
+    assert_eq!(resolve_vars("package main\nvar s Stack\n").unwrap_err(), "undefined: Stack");
+    assert_eq!(
+        resolve_vars("package main\ntype t struct { a [2]t }\n").unwrap_err(),
+        "invalid recursive type t"
+    );
+    assert_eq!(resolve_vars("package main\ntype a b\ntype b a\n").unwrap_err(), "invalid recursive type a");
+    assert_eq!(resolve_vars("package main\ntype a int\ntype a bool\n").unwrap_err(), "a redeclared in this block");
+}
+
+// Writes a parameter or result list back out in Go syntax
+fn render_params(list: &Option<ParamList>) -> String {
+    let Some(list) = list else {
+        return "()".to_string();
+    };
+    let groups: Vec<String> = list
+        .params
+        .iter()
+        .map(|(names, ty)| {
+            let ty = format!("{:?}", ty);
+            let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            if names.is_empty() {
+                return ty;
+            }
+            return format!("{} {}", names.join(", "), ty);
+        })
+        .collect();
+    return format!("({})", groups.join(", "));
+}
+
+// Each function's parameters and results
+fn signatures(src: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for decl in parse(src).decls {
+        if let TopLevelDecl::Func(name, func) = decl {
+            out.push(format!("{}{} {}", name, render_params(&func.params), render_params(&func.ret)));
+        }
+    }
+    return out;
+}
+
+#[test]
+fn result_lists() {
+    let sigs = signatures(concat!(
+        "package main\n",
+        "func a() {}\n",
+        "func b(x int) int { return x }\n",
+        "func c(int, string) (int, []string) { return 1, nil }\n",
+        "func d(x, y int, s string) (q, r int, err bool) { return }\n",
+        "func e(t T) (T) { return t }\n",
+        "func f(_ int, y int) (_ int, err bool) { return }\n",
+        "func g(_, _ string) {}\n",
+    ));
+    assert_eq!(
+        sigs,
+        [
+            "a() ()",
+            "b(x Named(\"int\")) (Named(\"int\"))",
+            "c(Named(\"int\"), Named(\"string\")) (Named(\"int\"), Slice(Named(\"string\")))",
+            "d(x, y Named(\"int\"), s Named(\"string\")) (q, r Named(\"int\"), err Named(\"bool\"))",
+            "e(t Named(\"T\")) (Named(\"T\"))",
+            "f(_ Named(\"int\"), y Named(\"int\")) (_ Named(\"int\"), err Named(\"bool\"))",
+            "g(_, _ Named(\"string\")) ()",
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "parameter type")]
+fn mixed_named_and_unnamed() {
+    parse("package main\nfunc f(a int, b) {}\n");
+}
+
+#[test]
+#[should_panic(expected = "parameter type")]
+fn blank_is_not_a_type() {
+    parse("package main\nfunc f(int, _) {}\n");
+}
+
+#[test]
+fn multi_value_return_and_assign() {
+    let ast = parse("package main\nfunc f() (int, int) {\n\ta, b := g()\n\ta, b = b, a\n\treturn a, b\n}\n");
+    let TopLevelDecl::Func(_, func) = &ast.decls[0] else {
+        panic!("expected a function");
+    };
+    let [Statement::SimpleStmt(SimpleStmt::ShortValDecl(names, values)), Statement::SimpleStmt(SimpleStmt::Assignment(lhs, None, rhs)), Statement::ReturnStmt(Some(results))] =
+        &func.body.stmts[..]
+    else {
+        panic!("unexpected statements: {:?}", func.body.stmts);
+    };
+    assert_eq!(names.len(), 2);
+    assert_eq!(values.exprs.len(), 1);
+    assert_eq!((lhs.exprs.len(), rhs.exprs.len()), (2, 2));
+    assert_eq!(results.exprs.len(), 2);
+}
+
+#[test]
+fn pointer_types_and_selectors() {
+    let ast = parse(concat!(
+        "package main\n",
+        "type Set struct { arr []bool; size int }\n",
+        "type node struct { val int; next *node }\n",
+        "var sets []*Set\nvar n *node\nvar m **node\n",
+    ));
+    let mut scope = Scope::new();
+    scope.declare_package_types(&ast).unwrap();
+    let mut vars = Vec::new();
+    for decl in &ast.decls {
+        if let TopLevelDecl::Decl(Declaration::Var(specs)) = decl {
+            vars.push(scope.resolve_type(specs[0].var_type.as_ref().unwrap()).unwrap());
+        }
+    }
+    assert_eq!(vars.iter().map(|ty| ty.to_string()).collect::<Vec<_>>(), ["[]*Set", "*node", "**node"]);
+
+    // Fields are reached through one pointer automatically
+    let next = Symbol::intern("next");
+    let next_type = scope.field_type(&vars[1], next).unwrap();
+    assert_eq!(next_type.to_string(), "*node");
+    assert_eq!(scope.field_type(&next_type, Symbol::intern("val")).unwrap().to_string(), "int");
+    let Type::Slice(set) = &vars[0] else {
+        panic!("expected a slice");
+    };
+    assert_eq!(scope.field_type(set, Symbol::intern("arr")).unwrap().to_string(), "[]bool");
+    assert_eq!(scope.field_type(&vars[2], next).unwrap_err(), "**node has no field next");
+    assert_eq!(scope.field_type(&vars[1], Symbol::intern("prev")).unwrap_err(), "*node has no field prev");
+}
+
+#[test]
+fn nil_is_predeclared() {
+    let scope = Scope::new();
+    assert!(matches!(scope.lookup(Symbol::intern("nil")), Some(Entity::Const(Type::Nil, _))));
+}
+
+#[test]
+fn methods_in_source_order() {
+    let ast = parse(concat!(
+        "package main\n",
+        "type Stack struct { items []int }\n",
+        "func (s *Stack) push(val int) {\n\ts.items = append(s.items, val)\n}\n",
+        "func main() {\n}\n",
+        "func (s Stack) size() int {\n\treturn len(s.items)\n}\n",
+        "func (*Stack) clear() {\n}\n",
+    ));
+    let recvs: Vec<(String, String, Option<String>, bool)> = ast
+        .methods()
+        .map(|method| {
+            let recv = &method.recv;
+            (recv.base.to_string(), method.name.to_string(), recv.name.map(|name| name.to_string()), recv.pointer)
+        })
+        .collect();
+    assert_eq!(
+        recvs,
+        [
+            ("Stack".to_string(), "push".to_string(), Some("s".to_string()), true),
+            ("Stack".to_string(), "size".to_string(), Some("s".to_string()), false),
+            ("Stack".to_string(), "clear".to_string(), None, true),
+        ]
+    );
+    // Methods stay among the other declarations, in the order they were written
+    let kinds: Vec<String> = ast.decls.iter().map(|decl| format!("{:?}", decl).split('(').next().unwrap().to_string()).collect();
+    assert_eq!(kinds, ["Decl", "Method", "Func", "Method", "Method"]);
+    // and the table points each type at its own
+    let table: Vec<(String, Vec<usize>)> =
+        ast.method_table.iter().map(|(base, indexes)| (base.to_string(), indexes.clone())).collect();
+    assert_eq!(table, [("Stack".to_string(), vec![1, 3, 4])]);
+    let names: Vec<String> = ast.methods_of(Symbol::intern("Stack")).map(|method| method.name.to_string()).collect();
+    assert_eq!(names, ["push", "size", "clear"]);
+    assert_eq!(ast.methods_of(Symbol::intern("main")).count(), 0);
+}
+
+#[test]
+fn duplicate_method() {
+    let ast = parse("package main\ntype Set struct { n int }\nfunc (s *Set) add(v int) {\n}\nfunc (s Set) add(v int) {\n}\n");
+    let mut scope = Scope::new();
+    scope.declare_package_types(&ast).unwrap();
+    assert_eq!(scope.declare_methods(&ast).unwrap_err(), "duplicate method Set.add");
+
+    // Different types can each have a method of the same name
+    let ast = parse("package main\ntype A int\ntype B int\nfunc (a A) f() {\n}\nfunc (b B) f() {\n}\n");
+    let mut scope = Scope::new();
+    scope.resolve_package(&ast).unwrap();
+}
+
+#[test]
+fn method_calls_on_values_and_pointers() {
+    let ast = parse(concat!(
+        "package main\n",
+        "type Set struct { arr []bool; size int }\n",
+        "func (s *Set) add(val int) {\n}\n",
+        "func (s Set) isEmpty() bool {\n\treturn s.size == 0\n}\n",
+    ));
+    let mut scope = Scope::new();
+    scope.declare_package_types(&ast).unwrap();
+    scope.declare_methods(&ast).unwrap();
+
+    let set = Type::Named(Symbol::intern("Set"));
+    let set_ptr = Type::Pointer(Box::new(set.clone()));
+    for base in [&set, &set_ptr] {
+        for name in ["add", "isEmpty"] {
+            let Ok(Selection::Method(method)) = scope.select(&ast, base, Symbol::intern(name)) else {
+                panic!("{}.{} should be a method", base, name);
+            };
+            assert_eq!(method.name, name);
+        }
+        let Ok(Selection::Field(size)) = scope.select(&ast, base, Symbol::intern("size")) else {
+            panic!("{}.size should be a field", base);
+        };
+        assert_eq!(size.to_string(), "int");
+    }
+    assert_eq!(scope.select(&ast, &set, Symbol::intern("remove")).unwrap_err(), "Set has no field or method remove");
+}
+
+#[test]
+fn method_receivers_checked() {
+    let mut scope = Scope::new();
+    let ast = parse("package main\nfunc (p *Point) norm() int {\n\treturn 0\n}\n");
+    assert_eq!(scope.declare_methods(&ast).unwrap_err(), "undefined: Point");
+
+    let ast = parse("package main\nfunc (i int) double() int {\n\treturn i * 2\n}\n");
+    assert_eq!(scope.declare_methods(&ast).unwrap_err(), "invalid receiver type int");
+
+    // A named pointer type can't have methods, even through another name
+    let ast = parse("package main\ntype T struct { x int }\ntype P *T\ntype Q P\nfunc (p P) f() {\n}\n");
+    let mut pointers = Scope::new();
+    pointers.declare_package_types(&ast).unwrap();
+    assert_eq!(pointers.declare_methods(&ast).unwrap_err(), "invalid receiver type P (pointer or interface type)");
+    let ast = parse("package main\ntype T struct { x int }\ntype P *T\ntype Q P\nfunc (q *Q) f() {\n}\n");
+    assert_eq!(pointers.declare_methods(&ast).unwrap_err(), "invalid receiver type Q (pointer or interface type)");
+
+    // Methods are checked in source order, so the first bad one is reported
+    let ast = parse("package main\nfunc (z Zeta) f() {\n}\nfunc (a Alpha) f() {\n}\nfunc (m Mid) f() {\n}\n");
+    assert_eq!(scope.declare_methods(&ast).unwrap_err(), "undefined: Zeta");
+
+    let ast = parse("package main\ntype Set struct { size int }\nfunc (s Set) size() int {\n\treturn 0\n}\n");
+    scope.declare_package_types(&ast).unwrap();
+    assert_eq!(scope.declare_methods(&ast).unwrap_err(), "field and method with the same name size");
+}
+
+#[test]
+fn package_level_types_resolved() {
+    let resolve = |src: &str| Scope::new().resolve_package(&parse(src));
+    assert_eq!(resolve("package main\nvar x undefinedType\n").unwrap_err(), "undefined: undefinedType");
+    assert_eq!(resolve("package main\nfunc f(x Undefined) {\n}\n").unwrap_err(), "undefined: Undefined");
+    assert_eq!(resolve("package main\nfunc f() (int, []Missing) {\n\treturn 0, nil\n}\n").unwrap_err(), "undefined: Missing");
+    assert_eq!(resolve("package main\ntype T struct { x int }\nfunc (t T) f(u *U) {\n}\n").unwrap_err(), "undefined: U");
+    assert_eq!(resolve("package main\nfunc (p *Point) norm() {\n}\n").unwrap_err(), "undefined: Point");
+
+    // Types can be used before they're declared
+    resolve("package main\nvar p Point\nfunc f(ps []Point) (q *Point) {\n\treturn nil\n}\ntype Point struct { x int }\n").unwrap();
+    // A function can't share a name with a type or a variable
+    assert_eq!(resolve("package main\ntype f int\nfunc f() {\n}\n").unwrap_err(), "f redeclared in this block");
+    assert_eq!(resolve("package main\nvar f int\nfunc f() {\n}\n").unwrap_err(), "f redeclared in this block");
+    assert_eq!(resolve("package main\nvar x, y int\nvar y = 2\n").unwrap_err(), "y redeclared in this block");
+    // but there can be any number of init functions
+    resolve("package main\nfunc init() {\n}\nfunc init() {\n}\n").unwrap();
+}
+
+#[test]
+fn package_vars_shadow_predeclared_names() {
+    let ast = parse("package main\nvar len = 3\nvar p, q []Point\ntype Point struct { x int }\n");
+    let mut scope = Scope::new();
+    scope.resolve_package(&ast).unwrap();
+    assert!(matches!(scope.lookup(Symbol::intern("len")), Some(Entity::Var(None))));
+    for name in ["p", "q"] {
+        let Some(Entity::Var(Some(ty))) = scope.lookup(Symbol::intern(name)) else {
+            panic!("{} should be a var of known type", name);
+        };
+        assert_eq!(ty.to_string(), "[]Point");
+    }
+}
+
+#[test]
+fn test_go_methods() {
+    let mut lex = Lexer::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/test.go")).unwrap();
+    let ast = parser::parse(&mut lex);
+    let mut scope = Scope::new();
+    scope.resolve_package(&ast).unwrap();
+    for (base, names) in [
+        ("Stack", &["pop", "push", "isEmpty", "String"][..]),
+        ("Set", &["remove", "add", "possValues", "String", "isEmpty"][..]),
+    ] {
+        let methods: Vec<String> = ast.methods_of(Symbol::intern(base)).map(|method| method.name.to_string()).collect();
+        assert_eq!(methods, names);
+    }
+}
+
+#[test]
+fn import_aliases() {
+    let ast = parse("package main\nimport \"fmt\"\nimport (\n\tf \"fmt\"\n\t_ \"os\"\n\t. \"math\"\n)\n");
+    let imports: Vec<(Option<String>, String)> = ast
+        .decls
+        .iter()
+        .map(|decl| match decl {
+            TopLevelDecl::Import(alias, path) => (alias.map(|alias| alias.to_string()), path.clone()),
+            _ => panic!("expected an import"),
+        })
+        .collect();
+    assert_eq!(
+        imports,
+        [
+            (None, "fmt".to_string()),
+            (Some("f".to_string()), "fmt".to_string()),
+            (Some("_".to_string()), "os".to_string()),
+            (Some(".".to_string()), "math".to_string()),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "<input>:2:9: Expected: import path")]
+fn import_needs_a_path() {
+    parse("package main\nimport f\n");
+}
+
+#[test]
+#[should_panic(expected = "<input>:2:1: Expected: Func")]
+fn unexpected_top_level_token() {
+    parse("package main\n42\n");
+}
+
+#[test]
+fn statements_in_function_bodies() {
+    let ast = parse(concat!(
+        "package main\n",
+        "func main() {\n",
+        "\tprintln(1, 2)\n\tprint()\n",
+        "\tfor {\n\t\tbreak\n\t}\n",
+        "\tswitch {\n\tcase true:\n\t\tfallthrough\n\tdefault:\n\t\tcontinue\n\t}\n",
+        "\t{\n\t\tx := 1\n\t}\n",
+        "\tif x {\n\t} else {\n\t\treturn\n\t}\n",
+        "}\n",
+    ));
+    let TopLevelDecl::Func(_, func) = &ast.decls[0] else {
+        panic!("expected a function");
+    };
+    let kinds: Vec<String> = func.body.stmts.iter().map(|stmt| format!("{:?}", stmt).split('(').next().unwrap().to_string()).collect();
+    assert_eq!(kinds, ["PrintStmt", "PrintStmt", "ForStmt", "SwitchStmt", "StmtList", "IfStmt"]);
+    let Statement::SwitchStmt(_, _, cases) = &func.body.stmts[3] else {
+        panic!("expected a switch");
+    };
+    assert!(matches!(cases[0].stmt.stmts[..], [Statement::FallthroughStmt]));
+    assert!(matches!(cases[1].stmt.stmts[..], [Statement::ContinueStmt]));
+}
+
+// The function called by every call statement in main, in order
+fn called_funcs(ast: &AST) -> Vec<&PrimaryExpr> {
+    let mut out = Vec::new();
+    for decl in &ast.decls {
+        let TopLevelDecl::Func(name, func) = decl else { continue };
+        if *name != "main" {
+            continue;
+        }
+        for stmt in &func.body.stmts {
+            if let Statement::SimpleStmt(SimpleStmt::Assignment(_, _, values)) = stmt {
+                if let Expression::PrimaryExpr(prim) = &values.exprs[0] {
+                    if let PrimaryExpr::Call(Some(func), _, _) = prim.as_ref() {
+                        out.push(func.as_ref());
+                    }
+                }
+            }
+        }
+    }
+    return out;
+}
+
+#[test]
+fn builtins_are_ordinary_calls() {
+    let ast = parse("package main\nfunc main() {\n\ts = append(s, 1, 2)\n\tn = len(s)\n\tc = cap(s)\n}\n");
+    let scope = Scope::new();
+    let builtins: Vec<Option<Builtin>> = called_funcs(&ast).into_iter().map(|func| scope.builtin(func)).collect();
+    assert_eq!(builtins, [Some(Builtin::Append), Some(Builtin::Len), Some(Builtin::Cap)]);
+
+    // A package can declare its own len, with whatever signature it likes
+    let ast = parse("package main\nfunc len(a, b int) int {\n\treturn a\n}\nfunc main() {\n\tn = len(x, y)\n}\n");
+    let mut package = Scope::new();
+    package.resolve_package(&ast).unwrap();
+    assert_eq!(package.builtin(called_funcs(&ast)[0]), None);
+    assert!(matches!(package.lookup(Symbol::intern("len")), Some(Entity::Func)));
+}
@@ -0,0 +1,219 @@
+#![allow(clippy::needless_return)]
+
+use golite::ast::{
+    CompositeLit, Declaration, Element, Expression, Literal, Operand, PrimaryExpr, SimpleStmt, Statement, TopLevelDecl,
+};
+use golite::lexer::Lexer;
+use golite::parser;
+
+// Writes an expression with every operator application in parentheses
+fn render(expr: &Expression) -> String {
+    match expr {
+        Expression::Binary(left, op, right) => {
+            return format!("({} {:?} {})", render(left), op, render(right));
+        }
+        Expression::Unary(op, operand) => return format!("({:?} {})", op, render(operand)),
+        Expression::PrimaryExpr(prim) => return render_primary(prim),
+    }
+}
+
+fn render_primary(prim: &PrimaryExpr) -> String {
+    match prim {
+        PrimaryExpr::Operand(Operand::Op(name)) => return name.to_string(),
+        PrimaryExpr::Operand(Operand::Literal(Literal::Integer(num))) => return num.to_string(),
+        PrimaryExpr::Operand(Operand::Expr(inner)) => return render(inner),
+        PrimaryExpr::Operand(Operand::Literal(Literal::String(text))) => return format!("{:?}", text),
+        PrimaryExpr::Operand(Operand::Literal(Literal::Composite(lit))) => return render_lit(lit),
+        PrimaryExpr::Selector(base, name) => return format!("{}.{}", render_primary(base), name),
+        PrimaryExpr::Index(base, index) => return format!("{}[{}]", render_primary(base), render(index)),
+        PrimaryExpr::Slice(base, low, high) => {
+            let low = low.as_ref().map(render).unwrap_or_default();
+            let high = high.as_ref().map(render).unwrap_or_default();
+            return format!("{}[{}:{}]", render_primary(base), low, high);
+        }
+        PrimaryExpr::Call(Some(func), type_arg, args) => {
+            let mut rendered: Vec<String> = type_arg.iter().map(|ty| ty.to_string()).collect();
+            rendered.extend(args.iter().flat_map(|list| list.exprs.iter().map(render)));
+            return format!("{}({})", render_primary(func), rendered.join(", "));
+        }
+        other => return format!("{:?}", other),
+    }
+}
+
+fn render_element(elem: &Element) -> String {
+    match elem {
+        Element::Expr(expr) => return render(expr),
+        Element::Lit(lit) => return render_lit(lit),
+    }
+}
+
+fn render_lit(lit: &CompositeLit) -> String {
+    let elems: Vec<String> = lit
+        .elems
+        .iter()
+        .map(|elem| match &elem.key {
+            Some(key) => format!("{}: {}", render_element(key), render_element(&elem.value)),
+            None => render_element(&elem.value),
+        })
+        .collect();
+    let lit_type = lit.lit_type.as_ref().map(|ty| ty.to_string()).unwrap_or_default();
+    return format!("{}{{{}}}", lit_type, elems.join(", "));
+}
+
+// Parses `var x = <src>` and renders the initializer
+fn parse(src: &str) -> String {
+    let mut lex = Lexer::from_source(&format!("package main\nvar x = {}\n", src));
+    let ast = parser::parse(&mut lex);
+    if let TopLevelDecl::Decl(Declaration::Var(specs)) = &ast.decls[0] {
+        return render(&specs[0].expr_list.as_ref().unwrap().exprs[0]);
+    }
+    panic!("expected a var declaration");
+}
+
+#[test]
+fn precedence_levels() {
+    assert_eq!(parse("a + b * c - d"), "((a Plus (b Times c)) Minus d)");
+    assert_eq!(parse("x < y && y < z"), "((x Less y) LogAnd (y Less z))");
+    assert_eq!(parse("a || b && c"), "(a LogOr (b LogAnd c))");
+    assert_eq!(parse("a == b | c &^ d"), "(a EqualEqual (b Or (c AndNot d)))");
+    assert_eq!(parse("1 << 2 + 3 >> 4"), "((1 LShift 2) Plus (3 RShift 4))");
+}
+
+#[test]
+fn left_associative() {
+    assert_eq!(parse("a - b - c"), "((a Minus b) Minus c)");
+    assert_eq!(parse("a / b * c % d"), "(((a Divide b) Times c) Mod d)");
+    assert_eq!(parse("a != b == c"), "((a NotEqual b) EqualEqual c)");
+}
+
+#[test]
+fn unary_and_parens() {
+    assert_eq!(parse("-a * b"), "((Minus a) Times b)");
+    assert_eq!(parse("!a && ^b != c"), "((Not a) LogAnd ((Xor b) NotEqual c))");
+    assert_eq!(parse("(a + b) * c"), "((a Plus b) Times c)");
+}
+
+#[test]
+fn postfix_chains() {
+    assert_eq!(parse("sudoku[j][i]"), "sudoku[j][i]");
+    assert_eq!(parse("rowSets[i].add(j)"), "rowSets[i].add(j)");
+    assert_eq!(parse("f(x).y[z]"), "f(x).y[z]");
+    assert_eq!(parse("a.b.c()()"), "a.b.c()()");
+    assert_eq!(parse("s[1:][:n][i:j]"), "s[1:][:n][i:j]");
+    assert_eq!(parse("-xs[0].v * 2"), "((Minus xs[0].v) Times 2)");
+}
+
+#[test]
+fn composite_literals() {
+    assert_eq!(parse("[][]int{{5, 3}, {6}}"), "[][]int{{5, 3}, {6}}");
+    assert_eq!(parse("Stack{}"), "Stack{}");
+    assert_eq!(parse("&Set{arr: make([]bool, 9)}"), "(AddrOf Set{arr: make([]bool, 9)})");
+    assert_eq!(parse("[3]point{{x: 1}, 2: {y: 2}}"), "[3]point{{x: 1}, 2: {y: 2}}");
+    assert_eq!(parse("T{1, 2,\n}.a[0]"), "T{1, 2}.a[0]");
+}
+
+// Parses a function body and returns its statements
+fn parse_body(body: &str) -> Vec<Statement> {
+    let mut lex = Lexer::from_source(&format!("package main\nfunc f() {{\n{}\n}}\n", body));
+    let ast = parser::parse(&mut lex);
+    if let Some(TopLevelDecl::Func(_, func)) = ast.decls.into_iter().next() {
+        return func.body.stmts;
+    }
+    panic!("expected a function");
+}
+
+#[test]
+fn composite_literals_in_headers() {
+    // A bare type name followed by { is the condition, then the body
+    let stmts = parse_body("if x == T {\n\tx++\n}");
+    let Statement::IfStmt(None, cond, body, None) = &stmts[0] else {
+        panic!("expected an if statement");
+    };
+    assert_eq!(render(cond), "(x EqualEqual T)");
+    assert_eq!(body.stmts.len(), 1);
+
+    // Parentheses, brackets, and explicit literal types make it a literal again
+    let stmts = parse_body("if x == (T{}) {\n}\nfor i := 0; i < len([]int{1}); i++ {\n}\nswitch f(T{1}) {\n}");
+    let Statement::IfStmt(None, cond, _, None) = &stmts[0] else {
+        panic!("expected an if statement");
+    };
+    assert_eq!(render(cond), "(x EqualEqual T{})");
+    let Statement::ForStmt(Some(clause), _) = &stmts[1] else {
+        panic!("expected a for statement");
+    };
+    assert_eq!(render(clause.expr.as_ref().unwrap()), "(i Less len([]int{1}))");
+    let Statement::SwitchStmt(None, Some(tag), _) = &stmts[2] else {
+        panic!("expected a switch statement");
+    };
+    assert_eq!(render(tag), "f(T{1})");
+
+    // A parenthesized type can start a literal as well as a conversion
+    let stmts = parse_body("for _, v := range ([]int{1, 2}) {\n}\nx := ([]int{1, 2}[1:])\ny := ([]int)(x)");
+    let Statement::RangeStmt(clause, _) = &stmts[0] else {
+        panic!("expected a range statement");
+    };
+    assert_eq!(render(&clause.expr), "[]int{1, 2}");
+    let Statement::SimpleStmt(SimpleStmt::ShortValDecl(_, values)) = &stmts[1] else {
+        panic!("expected a short variable declaration");
+    };
+    assert_eq!(render(&values.exprs[0]), "[]int{1, 2}[1:]");
+    let Statement::SimpleStmt(SimpleStmt::ShortValDecl(_, values)) = &stmts[2] else {
+        panic!("expected a short variable declaration");
+    };
+    let Expression::PrimaryExpr(conv) = &values.exprs[0] else { panic!("expected a conversion") };
+    assert!(matches!(conv.as_ref(), PrimaryExpr::Call(None, Some(_), Some(_))));
+}
+
+// Renders each range loop's clause as it was written
+fn range_clauses(body: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for stmt in parse_body(body) {
+        if let Statement::RangeStmt(clause, _) = stmt {
+            let vars: Vec<String> = clause.key.iter().chain(clause.value.iter()).map(render).collect();
+            if vars.is_empty() {
+                out.push(format!("range {}", render(&clause.expr)));
+                continue;
+            }
+            let op = if clause.define { ":=" } else { "=" };
+            out.push(format!("{} {} range {}", vars.join(", "), op, render(&clause.expr)));
+        }
+    }
+    return out;
+}
+
+#[test]
+fn range_loops() {
+    let clauses = range_clauses(concat!(
+        "for i, row := range sudoku {\n}\n",
+        "for _, v := range s[1:] {\n\tx += v\n}\n",
+        "for i := range []int{1, 2} {\n}\n",
+        "for a[0], b.c = range \"str\" {\n}\n",
+        "for range xs {\n}\n",
+        "for i := 0; i < n; i++ {\n}\n",
+    ));
+    assert_eq!(
+        clauses,
+        [
+            "i, row := range sudoku",
+            "_, v := range s[1:]",
+            "i := range []int{1, 2}",
+            "a[0], b.c = range \"str\"",
+            "range xs",
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "identifier")]
+fn range_define_needs_names() {
+    parse_body("for a[0] := range xs {\n}");
+}
+
+#[test]
+fn pointers() {
+    assert_eq!(parse("*p.next"), "(Deref p.next)");
+    assert_eq!(parse("&a[i] != nil"), "((AddrOf a[i]) NotEqual nil)");
+    assert_eq!(parse("**pp * *q"), "((Deref (Deref pp)) Times (Deref q))");
+    assert_eq!(parse("a & *b"), "(a And (Deref b))");
+    assert_eq!(parse("make([]*Set, 9)"), "make([]*Set, 9)");
+}
@@ -0,0 +1,57 @@
+#![allow(clippy::needless_return)]
+
+use golite::features;
+use golite::lexer::Lexer;
+
+// Each unsupported construct found, as line:col and the feature's name
+fn check(src: &str) -> Vec<String> {
+    let (toks, _) = Lexer::from_source(src).tokenize();
+    return features::check(&toks)
+        .iter()
+        .map(|diag| {
+            let name = diag.msg.strip_prefix("feature ").and_then(|msg| msg.split(" is not part of GoLite: ").next());
+            return format!("{}:{} {}", diag.span.line, diag.span.col, name.unwrap());
+        })
+        .collect();
+}
+
+#[test]
+fn each_unsupported_construct() {
+    let cases = [
+        ("var c chan int", "1:7 channel types"),
+        ("x := <-c", "1:6 channel operations"),
+        ("go f()", "1:1 goroutines"),
+        ("select {}", "1:1 select statements"),
+        ("goto end", "1:1 goto statements"),
+        ("defer f()", "1:1 deferred calls"),
+        ("type T interface{}", "1:8 interface types"),
+        ("var m map[string]int", "1:7 map types"),
+        ("const x = 1", "1:1 constant declarations"),
+        ("func f(xs ...int) {}", "1:11 variadic parameters"),
+        ("f(xs...)", "1:5 variadic arguments"),
+        ("s = append(s, t..., )", "1:16 variadic arguments"),
+        ("x := [...]int{1}", "1:7 inferred array lengths"),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(check(src), [expected], "checking {}", src);
+    }
+}
+
+#[test]
+fn full_messages() {
+    let (toks, _) = Lexer::from_source("x := [...]int{1}\nvar m map[int]bool\n").tokenize();
+    let msgs: Vec<String> = features::check(&toks).iter().map(|diag| diag.to_string()).collect();
+    assert_eq!(
+        msgs,
+        [
+            "<input>:1:7: feature inferred array lengths is not part of GoLite: write the length out, as in [3]int",
+            "<input>:2:7: feature map types is not part of GoLite: use a slice, or a slice of structs holding key and value",
+        ]
+    );
+}
+
+#[test]
+fn golite_is_clean() {
+    let src = "package main\nfunc main() {\n\tvar a [3]int\n\tfor i := range a {\n\t\tprintln(a[i:])\n\t}\n}\n";
+    assert!(check(src).is_empty());
+}
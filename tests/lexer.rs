@@ -0,0 +1,224 @@
+#![allow(clippy::needless_return)]
+
+use std::io::{self, Read};
+
+use golite::lexer::{Lexer, Token, TriviaKind};
+
+// The kinds of every token before EOF
+fn kinds(lex: Lexer) -> Vec<&'static str> {
+    let (toks, _) = lex.tokenize();
+    return toks.iter().filter(|(tok, _)| !tok.same_type(&Token::EOF)).map(|(tok, _)| tok.kind()).collect();
+}
+
+struct Broken;
+
+impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        return Err(io::Error::other("disk on fire"));
+    }
+}
+
+#[test]
+fn reader_errors_are_returned() {
+    let err = Lexer::new(Broken).err().expect("expected an error");
+    assert_eq!(err.to_string(), "disk on fire");
+    assert!(Lexer::named(Broken, "main.go").is_err());
+}
+
+#[test]
+fn borrowed_readers() {
+    // The input is read up front, so the reader doesn't have to outlive the lexer
+    let src = String::from("x := 1\n");
+    let lex = Lexer::named(src.as_bytes(), "main.go").unwrap();
+    assert_eq!(kinds(lex), ["Ident", "Assignment", "Int", "Semicolon"]);
+}
+
+// Every token before EOF, then every lexical error, each with its position
+fn render(src: &[u8]) -> Vec<String> {
+    let (toks, diagnostics) = Lexer::from_bytes(src).tokenize();
+    let mut out = Vec::new();
+    for (tok, span) in toks {
+        if !tok.same_type(&Token::EOF) {
+            out.push(format!("{}:{} {:?}", span.line, span.col, tok));
+        }
+    }
+    for diag in diagnostics {
+        out.push(format!("{}:{} error: {}", diag.span.line, diag.span.col, diag.msg));
+    }
+    return out;
+}
+
+#[test]
+fn multi_byte_identifiers() {
+    assert_eq!(
+        render("héllo wörld_2 日本語 x٣ _ö".as_bytes()),
+        [
+            "1:1 Ident(\"héllo\")",
+            "1:7 Ident(\"wörld_2\")",
+            "1:15 Ident(\"日本語\")",
+            "1:19 Ident(\"x٣\")",
+            "1:22 Ident(\"_ö\")",
+            "1:24 Semicolon",
+        ]
+    );
+}
+
+#[test]
+fn only_decimal_digits_in_identifiers() {
+    // ² is a number but not a decimal digit, so it can't continue a name
+    assert_eq!(
+        render("x² y１".as_bytes()),
+        ["1:1 Ident(\"x\")", "1:4 Ident(\"y１\")", "1:6 Semicolon", "1:2 error: invalid character U+00B2 '²'"]
+    );
+    // Nor can a digit start one
+    assert_eq!(render("٣x".as_bytes()), ["1:2 Ident(\"x\")", "1:3 Semicolon", "1:1 error: invalid character U+0663 '٣'"]);
+    // Letter numbers and combining marks are alphabetic, but not letters
+    assert_eq!(
+        render("xⅰ aͅb".as_bytes()),
+        [
+            "1:1 Ident(\"x\")",
+            "1:4 Ident(\"a\")",
+            "1:6 Ident(\"b\")",
+            "1:7 Semicolon",
+            "1:2 error: invalid character U+2170 'ⅰ'",
+            "1:5 error: invalid character U+0345 '\\u{345}'",
+        ]
+    );
+}
+
+#[test]
+fn byte_order_marks() {
+    // Skipped at the very start, without counting as a column
+    assert_eq!(render(b"\xef\xbb\xbfx"), ["1:1 Ident(\"x\")", "1:2 Semicolon"]);
+    assert_eq!(
+        render(b"x\xef\xbb\xbfy"),
+        ["1:1 Ident(\"x\")", "1:3 Ident(\"y\")", "1:4 Semicolon", "1:2 error: invalid BOM in the middle of the file"]
+    );
+    assert_eq!(
+        render(b"\xef\xbb\xbf\xef\xbb\xbfx"),
+        ["1:2 Ident(\"x\")", "1:3 Semicolon", "1:1 error: invalid BOM in the middle of the file"]
+    );
+}
+
+#[test]
+fn malformed_utf8() {
+    let bad = |col: u32| format!("1:{} error: invalid UTF-8 encoding", col);
+    let cases: [(&[u8], Vec<String>); 5] = [
+        // A byte that never appears in UTF-8
+        (b"a\xffb", vec!["1:1 Ident(\"a\")".into(), "1:3 Ident(\"b\")".into(), "1:4 Semicolon".into(), bad(2)]),
+        // An overlong encoding of /, reported once per byte
+        (b"a\xc0\xafb", vec!["1:1 Ident(\"a\")".into(), "1:4 Ident(\"b\")".into(), "1:5 Semicolon".into(), bad(2), bad(3)]),
+        // A sequence cut short
+        (b"a\xe2\x82 b", vec!["1:1 Ident(\"a\")".into(), "1:4 Ident(\"b\")".into(), "1:5 Semicolon".into(), bad(2)]),
+        // An encoded surrogate
+        (b"a\xed\xa0\x80b", vec!["1:1 Ident(\"a\")".into(), "1:3 Ident(\"b\")".into(), "1:4 Semicolon".into(), bad(2)]),
+        // Past U+10FFFF
+        (b"a\xf4\x90\x80\x80b", vec!["1:1 Ident(\"a\")".into(), "1:3 Ident(\"b\")".into(), "1:4 Semicolon".into(), bad(2)]),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(render(src), expected, "lexing {:?}", src);
+    }
+}
+
+#[test]
+fn nul_is_not_the_end() {
+    assert_eq!(
+        render(b"x\0y\n"),
+        ["1:1 Ident(\"x\")", "1:3 Ident(\"y\")", "1:4 Semicolon", "1:2 error: invalid NUL character"]
+    );
+}
+
+#[test]
+fn peek_nth_looks_ahead_without_consuming() {
+    let mut lex = Lexer::from_source("a + b");
+    assert_eq!(lex.peek_nth(2), &Token::Ident("b".into()));
+    assert_eq!(lex.peek_nth_span(2).col, 5);
+    assert_eq!(lex.peek_nth(0), &Token::Ident("a".into()));
+    assert_eq!(lex.peek(), &Token::Ident("a".into()));
+    // Past the end, every token is EOF
+    assert!(lex.peek_nth(4).same_type(&Token::EOF));
+    assert!(lex.peek_nth(9).same_type(&Token::EOF));
+    assert_eq!(lex.next_token(), Token::Ident("a".into()));
+    assert!(lex.peek_nth(1).same_type(&Token::Ident("b".into())));
+}
+
+#[test]
+fn rewind_after_lookahead() {
+    let mut lex = Lexer::from_source("x := $ 1\n");
+    lex.next_token();
+    let start = lex.checkpoint();
+    assert!(lex.next_token().same_type(&Token::Assignment));
+    assert!(lex.peek_nth(1).same_type(&Token::Semicolon));
+    assert_eq!(lex.next_token(), Token::Int(1));
+
+    lex.rewind(start);
+    assert!(lex.peek().same_type(&Token::Assignment));
+    assert_eq!(lex.span().col, 1);
+    let rest: Vec<&str> = lex.by_ref().map(|tok| tok.kind()).collect();
+    assert_eq!(rest, ["Assignment", "Int", "Semicolon"]);
+    // Tokens are only scanned once, so the bad character is only reported once
+    assert_eq!(lex.diagnostics().len(), 1);
+}
+
+#[test]
+fn iteration_stops_before_eof() {
+    let mut lex = Lexer::from_source("f()");
+    let toks: Vec<&str> = lex.by_ref().map(|tok| tok.kind()).collect();
+    assert_eq!(toks, ["Ident", "LParen", "RParen", "Semicolon"]);
+    assert_eq!(lex.next(), None);
+    assert!(lex.peek().same_type(&Token::EOF));
+    assert_eq!(Lexer::from_source("").count(), 0);
+}
+
+// Prints every token with its leading trivia, which should give back the input
+fn round_trip(src: &str) -> String {
+    let (toks, _) = Lexer::from_source(src).tokenize_with_trivia();
+    return toks.iter().map(|tok| tok.to_string()).collect();
+}
+
+#[test]
+fn trivia_round_trips() {
+    let cases = [
+        "package main\n\nfunc main() {\n\tx := 1 // one\n\t/* two */ y := 2\n}\n",
+        "var x = 1\r\nvar y = 2\r\n",
+        "\u{FEFF}package main\n",
+        "x := a $ b # c\n",
+        "x /* a\n  multi-line */ y\n",
+        "x + y /* never closed\n",
+        "// just a comment",
+        "",
+        "s := `raw\r\nstring` + \"\\x41\"\n",
+        "x\0y\u{FEFF}z\n",
+    ];
+    for src in cases {
+        assert_eq!(round_trip(src), src);
+    }
+}
+
+#[test]
+fn trivia_kinds() {
+    let (toks, diagnostics) = Lexer::from_source("\u{FEFF}a // c\r\n/* b */ $ b").tokenize_with_trivia();
+    let leading: Vec<Vec<(TriviaKind, &str)>> = toks
+        .iter()
+        .map(|tok| tok.leading.iter().map(|trivia| (trivia.kind, trivia.text.as_str())).collect())
+        .collect();
+    assert_eq!(
+        leading,
+        [
+            vec![(TriviaKind::Skipped, "\u{FEFF}")],
+            // The semicolon ending the line sits at the line break, taking up
+            // no text, so the break itself goes to the token after it
+            vec![(TriviaKind::Whitespace, " "), (TriviaKind::LineComment, "// c\r")],
+            vec![
+                (TriviaKind::Whitespace, "\n"),
+                (TriviaKind::BlockComment, "/* b */"),
+                (TriviaKind::Whitespace, " "),
+                (TriviaKind::Skipped, "$"),
+                (TriviaKind::Whitespace, " "),
+            ],
+            vec![],
+            vec![],
+        ]
+    );
+    assert_eq!(diagnostics.len(), 1);
+}
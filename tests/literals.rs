@@ -0,0 +1,120 @@
+#![allow(clippy::needless_return)]
+
+use golite::lexer::{Bytes, Lexer, Token};
+
+// Every token before the semicolon ending the line, then every lexical error
+fn lex(src: &str) -> Vec<String> {
+    let (toks, diagnostics) = Lexer::from_source(src).tokenize();
+    let mut out: Vec<String> = toks
+        .iter()
+        .filter(|(tok, _)| !tok.same_type(&Token::EOF) && !tok.same_type(&Token::Semicolon))
+        .map(|(tok, _)| format!("{:?}", tok))
+        .collect();
+    for diag in diagnostics {
+        out.push(format!("{}:{}: {}", diag.span.line, diag.span.col, diag.msg));
+    }
+    return out;
+}
+
+#[test]
+fn numbers() {
+    let cases = [
+        ("0755", vec!["Int(493)"]),
+        ("09", vec!["Int(0)", "1:1: invalid digit '9' in octal literal"]),
+        ("08.5", vec!["Float(8.5)"]),
+        ("1e9", vec!["Float(1000000000.0)"]),
+        ("0x1p-2", vec!["Float(0.25)"]),
+        ("0x.8p1", vec!["Float(1.0)"]),
+        ("0x1.8", vec!["Float(0.0)", "1:1: hexadecimal mantissa requires a 'p' exponent"]),
+        ("1_000_000", vec!["Int(1000000)"]),
+        ("0b_1010", vec!["Int(10)"]),
+        ("1__0", vec!["Int(0)", "1:1: '_' must separate successive digits"]),
+        ("0x", vec!["Int(0)", "1:1: hexadecimal literal has no digits"]),
+        ("9223372036854775807", vec!["Int(9223372036854775807)"]),
+        ("9223372036854775808", vec!["Int(0)", "1:1: integer literal 9223372036854775808 overflows int"]),
+        ("1e400", vec!["Float(0.0)", "1:1: floating-point literal 1e400 overflows float64"]),
+        // A malformed literal is consumed whole, so nothing trails the error
+        ("0o17.5", vec!["Float(0.0)", "1:1: invalid radix point in octal literal"]),
+        ("0b1e5", vec!["Float(0.0)", "1:1: 'e' exponent requires decimal mantissa"]),
+        ("1p5", vec!["Float(0.0)", "1:1: 'p' exponent requires hexadecimal mantissa"]),
+        ("1e+", vec!["Float(0.0)", "1:1: exponent has no digits"]),
+        ("2i", vec!["Int(0)", "1:1: imaginary literals are not supported"]),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(lex(src), expected, "lexing {}", src);
+    }
+}
+
+#[test]
+fn simple_escapes() {
+    assert_eq!(lex(r#""\a\b\f\n\r\t\v\\\"""#), [r#"String("\u{7}\u{8}\u{c}\n\r\t\u{b}\\\"")"#]);
+    assert_eq!(
+        lex(r"'\a' '\b' '\f' '\n' '\r' '\t' '\v' '\\' '\''"),
+        [
+            r"Rune('\u{7}')",
+            r"Rune('\u{8}')",
+            r"Rune('\u{c}')",
+            r"Rune('\n')",
+            r"Rune('\r')",
+            r"Rune('\t')",
+            r"Rune('\u{b}')",
+            r"Rune('\\')",
+            r"Rune('\'')",
+        ]
+    );
+    // Each quote can only be escaped inside its own kind of literal
+    assert_eq!(lex(r#""\'""#), [r#"String("")"#, r"1:1: unknown escape sequence '\''"]);
+    assert_eq!(lex(r#"'\"'"#), [r"Rune('\0')", r#"1:1: unknown escape sequence '\"'"#]);
+    assert_eq!(lex(r#""\q""#), [r#"String("")"#, r"1:1: unknown escape sequence '\q'"]);
+}
+
+#[test]
+fn numeric_escapes() {
+    assert_eq!(lex(r#""\101\x41é\U0001F600""#), [r#"String("AAé😀")"#]);
+    assert_eq!(lex(r"'\101' '\x41' 'é' '\U0001F600'"), [r"Rune('A')", r"Rune('A')", r"Rune('é')", r"Rune('😀')"]);
+    // Octal and \x escapes are single bytes in a string, even ones that aren't UTF-8
+    let (toks, diagnostics) = Lexer::from_source(r#""\xff\377\xc3\xa9""#).tokenize();
+    assert!(diagnostics.is_empty());
+    assert_eq!(toks[0].0, Token::String(Bytes(vec![0xff, 0xff, 0xc3, 0xa9])));
+    assert_eq!(format!("{:?}", toks[0].0), r#"String("\xff\xffé")"#);
+    // but the code point of that value in a rune
+    assert_eq!(lex(r"'\xff'"), ["Rune('ÿ')"]);
+
+    assert_eq!(lex(r#""\400""#), [r#"String("")"#, "1:1: octal escape value 256 > 255"]);
+    assert_eq!(lex(r"'\400'"), [r"Rune('\0')", "1:1: octal escape value 256 > 255"]);
+    assert_eq!(lex(r"'\x4'"), [r"Rune('\0')", r"1:1: illegal character '\'' in escape sequence"]);
+    assert_eq!(lex(r"'\u12'"), [r"Rune('\0')", r"1:1: illegal character '\'' in escape sequence"]);
+}
+
+#[test]
+fn surrogates_and_out_of_range() {
+    for src in [r#""\ud800""#, r#""\U0000DFFF""#, r#""\U00110000""#] {
+        assert_eq!(lex(src), [r#"String("")"#, "1:1: escape sequence is invalid Unicode code point"], "lexing {}", src);
+    }
+    assert_eq!(lex(r"'\uDC00'"), [r"Rune('\0')", "1:1: escape sequence is invalid Unicode code point"]);
+}
+
+#[test]
+fn rune_lengths() {
+    assert_eq!(lex("'é'"), ["Rune('é')"]);
+    assert_eq!(lex("'ab'"), ["Rune('b')", "1:1: more than one character in rune literal"]);
+    assert_eq!(lex("''"), [r"Rune('\0')", "1:1: empty rune literal or unescaped ' in rune literal"]);
+}
+
+#[test]
+fn unterminated() {
+    assert_eq!(lex("\"abc"), [r#"String("")"#, "1:1: string literal not terminated"]);
+    assert_eq!(lex("'a"), ["Rune('a')", "1:1: rune literal not terminated"]);
+    assert_eq!(lex("`abc"), [r#"String("")"#, "1:1: raw string literal not terminated"]);
+    assert_eq!(lex("\"\\"), [r#"String("")"#, "1:1: escape sequence not terminated"]);
+    assert_eq!(lex("'\\"), [r"Rune('\0')", "1:1: escape sequence not terminated"]);
+    // An interpreted string can't span lines
+    assert_eq!(lex("x = \"ab\ny"), ["Ident(\"x\")", "Equal", r#"String("")"#, "Ident(\"y\")", "1:5: string literal not terminated"]);
+}
+
+#[test]
+fn raw_strings() {
+    // Carriage returns are dropped, and backslashes are just backslashes
+    assert_eq!(lex("`a\r\nb\r`"), [r#"String("a\nb")"#]);
+    assert_eq!(lex(r"`\n\x`"), [r#"String("\\n\\x")"#]);
+}
@@ -0,0 +1,45 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::path::Path;
+
+use golite::lexer::{Lexer, Token};
+
+// Renders every token with the position it starts at, one per line, followed
+// by any lexical errors
+fn render(src: &[u8]) -> String {
+    let (toks, diagnostics) = Lexer::from_bytes(src).tokenize();
+    let mut out = String::new();
+    for (tok, span) in toks {
+        if !tok.same_type(&Token::EOF) {
+            out += &format!("{}:{} {:?}\n", span.line, span.col, tok);
+        }
+    }
+    for diag in diagnostics {
+        out += &format!("{}:{} error: {}\n", diag.span.line, diag.span.col, diag.msg);
+    }
+    return out;
+}
+
+// Each corpus/semicolons/NAME.go must lex to the tokens listed in NAME.expected
+#[test]
+fn semicolon_corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/semicolons");
+    let mut failures = Vec::new();
+    let mut cases = 0;
+    for entry in fs::read_dir(&dir).expect("Couldn't read corpus") {
+        let path = entry.expect("Couldn't read corpus").path();
+        if path.extension().is_none_or(|ext| ext != "go") {
+            continue;
+        }
+        cases += 1;
+        let src = fs::read(&path).expect("Couldn't read case");
+        let expected = fs::read_to_string(path.with_extension("expected")).unwrap_or_default();
+        let got = render(&src);
+        if got != expected {
+            failures.push(format!("{}:\n--- expected\n{}--- got\n{}", path.display(), expected, got));
+        }
+    }
+    assert!(cases > 0, "no cases found in {}", dir.display());
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+
+use std::thread;
+
+use golite::symbol::Symbol;
+
+#[test]
+fn symbols_cross_threads() {
+    let main = Symbol::intern("shared_main");
+    let (from_thread, name) = thread::spawn(move || {
+        let sym = Symbol::intern("shared_thread");
+        return (sym, main.as_str());
+    })
+    .join()
+    .unwrap();
+    assert_eq!(name, "shared_main");
+    assert_eq!(from_thread.as_str(), "shared_thread");
+    assert_eq!(from_thread, Symbol::intern("shared_thread"));
+}
+
+#[test]
+fn interning_from_many_threads() {
+    let handles: Vec<_> = (0..8)
+        .map(|i| thread::spawn(move || (0..100).map(|j| Symbol::intern(&format!("name{}", (i + j) % 50))).collect::<Vec<_>>()))
+        .collect();
+    for handle in handles {
+        for sym in handle.join().unwrap() {
+            assert_eq!(sym, Symbol::intern(sym.as_str()));
+            assert!(sym.as_str().starts_with("name"));
+        }
+    }
+}